@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
+use rust_ray_tracer::geometry::{Bvh, HitEnum, HitableList, Hittable, Ray, Sphere, Vec3};
+use rust_ray_tracer::material::Lambertian;
+
+fn random_spheres(count: usize) -> HitableList {
+    let mut rng = Pcg64Mcg::seed_from_u64(0);
+    let material = Lambertian::new(Vec3::new(0.5, 0.5, 0.5));
+    let mut list = HitableList::new();
+    for _ in 0..count {
+        let center = Vec3::new(
+            rng.gen_range(-50.0..50.0),
+            rng.gen_range(-50.0..50.0),
+            rng.gen_range(-150.0..-50.0),
+        );
+        list.add(Sphere::new(
+            center,
+            rng.gen_range(0.2..1.0),
+            material.clone(),
+        ));
+    }
+    list
+}
+
+fn camera_rays() -> Vec<Ray> {
+    let mut rng = Pcg64Mcg::seed_from_u64(1);
+    (0..1_000)
+        .map(|_| {
+            let direction = Vec3::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5), -1.);
+            Ray::new(Vec3::zeros(), direction)
+        })
+        .collect()
+}
+
+fn trace(world: &HitEnum, rays: &[Ray]) -> usize {
+    rays.iter()
+        .filter(|ray| world.hit(ray, 0.001..f64::INFINITY).is_some())
+        .count()
+}
+
+fn bvh_vs_list(c: &mut Criterion) {
+    let rays = camera_rays();
+    let mut group = c.benchmark_group("1000 rays");
+    for count in [10, 100, 1_000, 10_000] {
+        let list = HitEnum::List(random_spheres(count));
+        let bvh = Bvh::new(random_spheres(count));
+        group.bench_with_input(BenchmarkId::new("HitableList", count), &list, |b, world| {
+            b.iter(|| trace(black_box(world), &rays))
+        });
+        group.bench_with_input(BenchmarkId::new("Bvh", count), &bvh, |b, world| {
+            b.iter(|| trace(black_box(world), &rays))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bvh_vs_list);
+criterion_main!(benches);
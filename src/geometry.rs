@@ -1,20 +1,34 @@
-use serde::{Deserialize, Serialize};
-
 mod vec3;
+#[cfg(test)]
+pub use vec3::{assert_near, assert_within};
 pub use vec3::{Color, Point3, Vec3};
 
 mod ray;
 pub use ray::Ray;
 
+mod aabb;
+pub use aabb::Aabb;
+
+mod matrix;
+pub use matrix::Matrix4;
+
+mod polynomial;
+
 mod hittable;
+pub use hittable::bvh::Bvh;
+pub use hittable::cone::Cone;
+pub use hittable::csg::{Csg, Operation};
+pub use hittable::cuboid::Cuboid;
+pub use hittable::cylinder::Cylinder;
+pub use hittable::disk::Disk;
 pub use hittable::hitable_list::HitableList;
+pub use hittable::mesh::Mesh;
+pub use hittable::model::{Model, ObjError};
 pub use hittable::plane::Plane;
+pub use hittable::quad::Quad;
+pub use hittable::quadric::{Quadric, QuadricCoefficients};
 pub use hittable::sphere::Sphere;
-pub use hittable::{HitRecord, Hittable};
-
-#[derive(Serialize, Deserialize)]
-pub struct SceneDescription {
-    pub hittable: HitableList,
-    pub camera_width: u32,
-    pub camera_height: u32,
-}
+pub use hittable::torus::Torus;
+pub use hittable::transform::Transform;
+pub use hittable::triangle::Triangle;
+pub use hittable::{Boundary, HitEnum, HitRecord, Hittable, Interval, Sampleable, Solid};
@@ -0,0 +1,132 @@
+use super::{Point3, Ray};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// An axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    /// The smallest box containing both corner points
+    pub fn new(a: Point3, b: Point3) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn surrounding(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Whether `point` is in the box, counting points up to `margin` outside it
+    pub fn contains(&self, point: Point3, margin: f64) -> bool {
+        (0..3)
+            .all(|axis| (self.min[axis] - margin..=self.max[axis] + margin).contains(&point[axis]))
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.max - self.min;
+        2. * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    /// Slab test for whether the ray passes through the box anywhere in `t_range`
+    pub fn hit(&self, ray: &Ray, t_range: Range<f64>) -> bool {
+        let (mut t_min, mut t_max) = (t_range.start, t_range.end);
+        for axis in 0..3 {
+            let inverse_direction = 1. / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction;
+            if inverse_direction < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // Written so a NaN from a zero-width slab leaves the bounds untouched
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Vec3;
+    use pretty_assertions::assert_eq;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::new(1., 1., 1.), Vec3::new(-1., -1., -1.))
+    }
+
+    #[test]
+    fn new_orders_corners() {
+        let aabb = unit_box();
+
+        assert_eq!(aabb.min, -Vec3::ones());
+        assert_eq!(aabb.max, Vec3::ones());
+    }
+
+    #[test]
+    fn surrounding_box_contains_both() {
+        let other = Aabb::new(Vec3::new(0., 0., 0.), Vec3::new(3., 0.5, 0.5));
+
+        let expected = Aabb::new(-Vec3::ones(), Vec3::new(3., 1., 1.));
+
+        assert_eq!(unit_box().surrounding(other), expected);
+        assert_eq!(expected.longest_axis(), 0);
+    }
+
+    #[test]
+    fn ray_through_box_hits() {
+        let ray = Ray::new(Vec3::new(-5., 0.5, 0.5), Vec3::i());
+
+        assert!(unit_box().hit(&ray, 0.0..f64::INFINITY));
+        assert!(!unit_box().hit(&ray, 0.0..3.0));
+    }
+
+    #[test]
+    fn ray_beside_box_misses() {
+        let ray = Ray::new(Vec3::new(-5., 2., 0.), Vec3::i());
+
+        assert!(!unit_box().hit(&ray, 0.0..f64::INFINITY));
+    }
+
+    #[test]
+    fn axis_parallel_ray_inside_slab_hits() {
+        let ray = Ray::new(Vec3::new(0., 0., -5.), Vec3::k());
+
+        assert!(unit_box().hit(&ray, 0.0..f64::INFINITY));
+    }
+
+    #[test]
+    fn flat_box_can_still_be_hit() {
+        let flat = Aabb::new(Vec3::new(-1., 0., -1.), Vec3::new(1., 0., 1.));
+        let ray = Ray::new(Vec3::new(0.2, 3., 0.1), -Vec3::j());
+
+        assert!(flat.hit(&ray, 0.0..f64::INFINITY));
+    }
+}
@@ -1,30 +1,280 @@
-use super::{Point3, Ray, Vec3};
-use crate::material::{Material, MaterialEnum};
+use super::{Aabb, Point3, Ray, Vec3};
+use crate::material::MaterialEnum;
+use crate::sampler::Sampler;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+pub mod bvh;
+pub mod cone;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
 pub mod hitable_list;
+pub mod mesh;
+pub mod model;
 pub mod plane;
+pub mod quad;
+pub mod quadric;
 pub mod sphere;
+pub mod torus;
+pub mod transform;
+pub mod triangle;
 
 pub trait Hittable {
     fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord>;
+
+    /// A box around the whole object, or `None` if it's unbounded
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Emissive shapes inside the object that can be sampled directly
+    fn lights(&self) -> Vec<HitEnum> {
+        Vec::new()
+    }
+
+    /// Files the object was read from, which anything saved about it is only good for as long
+    /// as they stay the same
+    fn source_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Shapes that can pick directions toward themselves, so shadow rays can be aimed at lights
+pub trait Sampleable {
+    /// A unit vector from `origin` toward a random point on the shape
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3;
+
+    /// Solid angle density of `sample_direction` picking the unit vector `direction`, which is
+    /// zero if it misses the shape
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64;
+}
+
+/// Closed shapes that can tell which stretches of a ray are inside them, so they can be
+/// combined by `Csg`
+pub trait Solid {
+    /// Where the ray's whole line, behind its origin as well as ahead, passes through the inside
+    /// of the shape, in order and without overlaps
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>>;
+}
+
+/// A stretch of a ray inside a solid
+#[derive(Clone, Copy)]
+pub struct Interval<'a> {
+    pub enter: Boundary<'a>,
+    pub exit: Boundary<'a>,
+}
+
+/// Where a ray crosses the surface of a solid
+#[derive(Clone, Copy)]
+pub struct Boundary<'a> {
+    pub t: f64,
+    /// Pointing out of the solid, whichever way the ray is going
+    pub outward_normal: Vec3,
+    pub material: &'a MaterialEnum,
+}
+
+impl Boundary<'_> {
+    fn record(&self, ray: &Ray) -> HitRecord {
+        HitRecord::new(
+            ray.at(self.t),
+            self.t,
+            ray,
+            self.outward_normal,
+            self.material.clone(),
+        )
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum HitEnum {
+    Bvh(bvh::Bvh),
+    Cone(cone::Cone),
+    Csg(csg::Csg),
+    Cuboid(cuboid::Cuboid),
+    Cylinder(cylinder::Cylinder),
+    Disk(disk::Disk),
     List(hitable_list::HitableList),
+    Mesh(mesh::Mesh),
+    Model(model::Model),
     Plane(plane::Plane),
+    Quad(quad::Quad),
+    Quadric(quadric::Quadric),
     Sphere(sphere::Sphere),
+    Torus(torus::Torus),
+    Transform(Box<transform::Transform>),
+    Triangle(triangle::Triangle),
 }
 
 impl Hittable for HitEnum {
     fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
         match self {
+            Self::Bvh(x) => x.hit(ray, t_range),
+            Self::Cone(x) => x.hit(ray, t_range),
+            Self::Csg(x) => x.hit(ray, t_range),
+            Self::Cuboid(x) => x.hit(ray, t_range),
+            Self::Cylinder(x) => x.hit(ray, t_range),
+            Self::Disk(x) => x.hit(ray, t_range),
             Self::List(x) => x.hit(ray, t_range),
+            Self::Mesh(x) => x.hit(ray, t_range),
+            Self::Model(x) => x.hit(ray, t_range),
             Self::Plane(x) => x.hit(ray, t_range),
+            Self::Quad(x) => x.hit(ray, t_range),
+            Self::Quadric(x) => x.hit(ray, t_range),
             Self::Sphere(x) => x.hit(ray, t_range),
+            Self::Torus(x) => x.hit(ray, t_range),
+            Self::Transform(x) => x.hit(ray, t_range),
+            Self::Triangle(x) => x.hit(ray, t_range),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self {
+            Self::Bvh(x) => x.bounding_box(),
+            Self::Cone(x) => x.bounding_box(),
+            Self::Csg(x) => x.bounding_box(),
+            Self::Cuboid(x) => x.bounding_box(),
+            Self::Cylinder(x) => x.bounding_box(),
+            Self::Disk(x) => x.bounding_box(),
+            Self::List(x) => x.bounding_box(),
+            Self::Mesh(x) => x.bounding_box(),
+            Self::Model(x) => x.bounding_box(),
+            Self::Plane(x) => x.bounding_box(),
+            Self::Quad(x) => x.bounding_box(),
+            Self::Quadric(x) => x.bounding_box(),
+            Self::Sphere(x) => x.bounding_box(),
+            Self::Torus(x) => x.bounding_box(),
+            Self::Transform(x) => x.bounding_box(),
+            Self::Triangle(x) => x.bounding_box(),
+        }
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        match self {
+            Self::Bvh(x) => x.lights(),
+            Self::Cone(x) => x.lights(),
+            Self::Csg(x) => x.lights(),
+            Self::Cuboid(x) => x.lights(),
+            Self::Cylinder(x) => x.lights(),
+            Self::Disk(x) => x.lights(),
+            Self::List(x) => x.lights(),
+            Self::Mesh(x) => x.lights(),
+            Self::Model(x) => x.lights(),
+            Self::Plane(x) => x.lights(),
+            Self::Quad(x) => x.lights(),
+            Self::Quadric(x) => x.lights(),
+            Self::Sphere(x) => x.lights(),
+            Self::Torus(x) => x.lights(),
+            Self::Transform(x) => x.lights(),
+            Self::Triangle(x) => x.lights(),
+        }
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Bvh(x) => x.source_files(),
+            Self::Cone(x) => x.source_files(),
+            Self::Csg(x) => x.source_files(),
+            Self::Cuboid(x) => x.source_files(),
+            Self::Cylinder(x) => x.source_files(),
+            Self::Disk(x) => x.source_files(),
+            Self::List(x) => x.source_files(),
+            Self::Mesh(x) => x.source_files(),
+            Self::Model(x) => x.source_files(),
+            Self::Plane(x) => x.source_files(),
+            Self::Quad(x) => x.source_files(),
+            Self::Quadric(x) => x.source_files(),
+            Self::Sphere(x) => x.source_files(),
+            Self::Torus(x) => x.source_files(),
+            Self::Transform(x) => x.source_files(),
+            Self::Triangle(x) => x.source_files(),
+        }
+    }
+}
+
+impl Sampleable for HitEnum {
+    /// Only spheres, triangles, quads and disks, and transforms of them, can be sampled;
+    /// anything else gives no directions
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        match self {
+            Self::Disk(x) => x.sample_direction(origin, sampler),
+            Self::Quad(x) => x.sample_direction(origin, sampler),
+            Self::Sphere(x) => x.sample_direction(origin, sampler),
+            Self::Transform(x) => x.sample_direction(origin, sampler),
+            Self::Triangle(x) => x.sample_direction(origin, sampler),
+            _ => Vec3::zeros(),
+        }
+    }
+
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        match self {
+            Self::Disk(x) => x.direction_pdf(origin, direction),
+            Self::Quad(x) => x.direction_pdf(origin, direction),
+            Self::Sphere(x) => x.direction_pdf(origin, direction),
+            Self::Transform(x) => x.direction_pdf(origin, direction),
+            Self::Triangle(x) => x.direction_pdf(origin, direction),
+            _ => 0.,
+        }
+    }
+}
+
+impl Solid for HitEnum {
+    /// Only spheres, boxes, cylinders, cones and tori, and transforms and combinations of them,
+    /// are solid; anything else is never inside
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        match self {
+            Self::Cone(x) => x.intervals(ray),
+            Self::Csg(x) => x.intervals(ray),
+            Self::Cuboid(x) => x.intervals(ray),
+            Self::Cylinder(x) => x.intervals(ray),
+            Self::Sphere(x) => x.intervals(ray),
+            Self::Torus(x) => x.intervals(ray),
+            Self::Transform(x) => x.intervals(ray),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl HitEnum {
+    /// Whether this is one of the closed shapes that implement `Solid`
+    pub fn is_solid(&self) -> bool {
+        match self {
+            Self::Cone(_)
+            | Self::Csg(_)
+            | Self::Cuboid(_)
+            | Self::Cylinder(_)
+            | Self::Sphere(_)
+            | Self::Torus(_) => true,
+            Self::Transform(x) => x.is_solid(),
+            _ => false,
+        }
+    }
+
+    /// Names of the scene's `objects` placed somewhere inside this that haven't been looked up
+    pub(crate) fn unresolved_names(&self) -> Vec<&str> {
+        match self {
+            Self::Bvh(x) => x.unresolved_names(),
+            Self::Csg(x) => x.unresolved_names(),
+            Self::List(x) => x.unresolved_names(),
+            Self::Transform(x) => x.unresolved_names(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Look up every name placed inside this among `objects`
+    pub(crate) fn resolve(self, objects: &BTreeMap<String, Arc<HitEnum>>) -> Result<Self, String> {
+        if self.unresolved_names().is_empty() {
+            return Ok(self);
         }
+        Ok(match self {
+            Self::Bvh(x) => Self::Bvh(x.resolve(objects)?),
+            Self::Csg(x) => Self::Csg(x.resolve(objects)?),
+            Self::List(x) => Self::List(x.resolve(objects)?),
+            Self::Transform(x) => Self::Transform(Box::new(x.resolve(objects)?)),
+            object => object,
+        })
     }
 }
 
@@ -34,6 +284,8 @@ pub struct HitRecord {
     pub t: f64,
     pub front_face: bool,
     pub material: MaterialEnum,
+    /// Which entry of the scene's list was hit, filled in by the `Bvh` holding the scene
+    pub object: usize,
 }
 
 impl HitRecord {
@@ -56,6 +308,7 @@ impl HitRecord {
             t,
             front_face,
             material,
+            object: 0,
         }
     }
 }
@@ -0,0 +1,388 @@
+use super::hitable_list::HitableList;
+use super::{Aabb, HitEnum, HitRecord, Hittable, Ray};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Most items a leaf is allowed to hold before it gets split
+const MAX_LEAF_SIZE: usize = 2;
+
+/// Deepest a tree can get, which a median split only reaches with more items than fit in memory
+const MAX_DEPTH: usize = 64;
+
+/// A bounding volume hierarchy over `objects`, so each ray only tests the objects whose
+/// boxes it passes through. Serializes as the plain list it was built from.
+///
+/// Every object is kept with the index of the entry of that list it came from, which hits on it
+/// report as [`HitRecord::object`].
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "HitableList", into = "HitableList")]
+pub struct Bvh {
+    objects: Vec<(usize, HitEnum)>,
+    tree: BvhTree,
+    /// Objects with no bounding box, like infinite planes, which every ray is tested against
+    unbounded: Vec<(usize, HitEnum)>,
+}
+
+impl Bvh {
+    pub fn new(list: HitableList) -> HitEnum {
+        HitEnum::Bvh(Self::from(list))
+    }
+}
+
+impl Bvh {
+    /// Build a tree over objects that are each kept with the index of the entry they came from
+    fn build(objects: impl IntoIterator<Item = (usize, HitEnum)>) -> Self {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for (id, object) in objects {
+            match object.bounding_box() {
+                Some(bounds) => bounded.push((bounds, (id, object))),
+                None => unbounded.push((id, object)),
+            }
+        }
+
+        let bounds = bounded
+            .iter()
+            .map(|(bounds, _)| *bounds)
+            .collect::<Vec<_>>();
+        let (tree, order) = BvhTree::build(&bounds);
+        let mut bounded = bounded.into_iter().map(Some).collect::<Vec<_>>();
+        let objects = order
+            .into_iter()
+            .map(|index| bounded[index].take().unwrap().1)
+            .collect();
+
+        Self {
+            objects,
+            tree,
+            unbounded,
+        }
+    }
+
+    pub(super) fn unresolved_names(&self) -> Vec<&str> {
+        self.objects
+            .iter()
+            .chain(&self.unbounded)
+            .flat_map(|(_, object)| object.unresolved_names())
+            .collect()
+    }
+
+    /// Look up every name placed in the tree among `objects`, then build it again around what
+    /// they turned out to be
+    pub(super) fn resolve(self, objects: &BTreeMap<String, Arc<HitEnum>>) -> Result<Self, String> {
+        let resolved = self
+            .objects
+            .into_iter()
+            .chain(self.unbounded)
+            .map(|(id, object)| Ok((id, object.resolve(objects)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self::build(resolved))
+    }
+}
+
+impl From<HitableList> for Bvh {
+    fn from(list: HitableList) -> Self {
+        Self::build(
+            list.into_iter()
+                .enumerate()
+                .flat_map(|(id, entry)| flatten(entry).into_iter().map(move |object| (id, object))),
+        )
+    }
+}
+
+impl From<Bvh> for HitableList {
+    fn from(bvh: Bvh) -> Self {
+        let mut list = HitableList::new();
+        bvh.objects
+            .into_iter()
+            .chain(bvh.unbounded)
+            .for_each(|(_, object)| list.add(object));
+        list
+    }
+}
+
+/// Pull the contents of nested lists up to the top level so they get their own place in the
+/// tree rather than sharing a single leaf.
+fn flatten(object: HitEnum) -> Vec<HitEnum> {
+    let list = match object {
+        HitEnum::List(list) => list,
+        HitEnum::Bvh(bvh) => bvh.into(),
+        object => return vec![object],
+    };
+    list.into_iter().flat_map(flatten).collect()
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let mut closest = self.tree.hit(ray, t_range.clone(), |index, t_range| {
+            let (id, object) = &self.objects[index];
+            let record = object.hit(ray, t_range)?;
+            Some((
+                record.t,
+                HitRecord {
+                    object: *id,
+                    ..record
+                },
+            ))
+        });
+        for (id, object) in &self.unbounded {
+            let t_max = closest.as_ref().map_or(t_range.end, |record| record.t);
+            if let Some(record) = object.hit(ray, t_range.start..t_max) {
+                closest = Some(HitRecord {
+                    object: *id,
+                    ..record
+                });
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if self.unbounded.is_empty() {
+            self.tree.bounds()
+        } else {
+            None
+        }
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        self.objects
+            .iter()
+            .chain(&self.unbounded)
+            .flat_map(|(_, object)| object.lights())
+            .collect()
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        self.objects
+            .iter()
+            .chain(&self.unbounded)
+            .flat_map(|(_, object)| object.source_files())
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BvhNode {
+    bounds: Aabb,
+    /// The first item of a leaf, or the second child of an interior node; the first child
+    /// always directly follows its parent
+    offset: usize,
+    /// Number of items in a leaf, zero for interior nodes
+    count: usize,
+    /// Axis an interior node's children were split along
+    axis: usize,
+}
+
+/// The node layout of a hierarchy, kept separate from the items it indexes so it can be
+/// shared by anything that has a list of bounded items.
+#[derive(Clone, Default)]
+pub(super) struct BvhTree {
+    nodes: Vec<BvhNode>,
+}
+
+impl BvhTree {
+    /// Build a tree over items with the given bounds. Leaves refer to ranges of items, so the
+    /// items need to be stored in the returned order, which lists their original indices.
+    pub(super) fn build(bounds: &[Aabb]) -> (Self, Vec<usize>) {
+        let mut tree = Self::default();
+        let mut order = (0..bounds.len()).collect::<Vec<_>>();
+        if !bounds.is_empty() {
+            tree.build_node(bounds, &mut order, 0);
+        }
+        (tree, order)
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], items: &mut [usize], offset: usize) {
+        let node_bounds = items
+            .iter()
+            .map(|&item| bounds[item])
+            .reduce(Aabb::surrounding)
+            .unwrap();
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            offset,
+            count: items.len(),
+            axis: 0,
+        });
+        if items.len() <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        let axis = items
+            .iter()
+            .map(|&item| {
+                let centroid = bounds[item].centroid();
+                Aabb::new(centroid, centroid)
+            })
+            .reduce(Aabb::surrounding)
+            .unwrap()
+            .longest_axis();
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |&lhs, &rhs| {
+            bounds[lhs].centroid()[axis]
+                .partial_cmp(&bounds[rhs].centroid()[axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let (left, right) = items.split_at_mut(middle);
+        self.build_node(bounds, left, offset);
+        let right_index = self.nodes.len();
+        self.build_node(bounds, right, offset + middle);
+
+        self.nodes[node_index] = BvhNode {
+            bounds: node_bounds,
+            offset: right_index,
+            count: 0,
+            axis,
+        };
+    }
+
+    pub(super) fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Find the closest hit, calling `hit_item` with the index of each item whose leaf the ray
+    /// reaches and the range of `t` still worth looking in. Hits are reported as their `t`
+    /// along with whatever the caller needs to know about them.
+    pub(super) fn hit<T>(
+        &self,
+        ray: &Ray,
+        t_range: Range<f64>,
+        mut hit_item: impl FnMut(usize, Range<f64>) -> Option<(f64, T)>,
+    ) -> Option<T> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut closest = None;
+        let mut t_max = t_range.end;
+        let mut stack = [0; MAX_DEPTH];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node_index = stack[stack_size];
+            let node = &self.nodes[node_index];
+            if !node.bounds.hit(ray, t_range.start..t_max) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for item in node.offset..node.offset + node.count {
+                    if let Some((t, hit)) = hit_item(item, t_range.start..t_max) {
+                        t_max = t;
+                        closest = Some(hit);
+                    }
+                }
+            } else {
+                // Visit the child nearer the ray origin first so later boxes can be culled
+                let (near, far) = if ray.direction[node.axis] < 0. {
+                    (node.offset, node_index + 1)
+                } else {
+                    (node_index + 1, node.offset)
+                };
+                stack[stack_size] = far;
+                stack[stack_size + 1] = near;
+                stack_size += 2;
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::{Plane, Sphere, Vec3};
+    use crate::material::Lambertian;
+    use pretty_assertions::assert_eq;
+
+    fn sphere_grid() -> HitableList {
+        let material = Lambertian::new(Vec3::ones());
+        let mut list = HitableList::new();
+        for x in -5..5 {
+            for y in -5..5 {
+                list.add(Sphere::new(
+                    Vec3::new(x as f64, y as f64, -10. - (x + y) as f64 * 0.1),
+                    0.3,
+                    material.clone(),
+                ));
+            }
+        }
+        list
+    }
+
+    #[test]
+    fn bvh_matches_linear_search() {
+        let list = sphere_grid();
+        let bvh = Bvh::from(list.clone());
+
+        for x in -30..30 {
+            for y in -30..30 {
+                let direction = Vec3::new(x as f64 * 0.02, y as f64 * 0.02, -1.);
+                let ray = Ray::new(Vec3::new(0.1, -0.2, 0.), direction);
+                let expected = list.hit(&ray, 0.001..f64::INFINITY).map(|record| record.t);
+                let actual = bvh.hit(&ray, 0.001..f64::INFINITY).map(|record| record.t);
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn unbounded_objects_are_kept_out_of_the_tree() {
+        let mut list = sphere_grid();
+        list.add(Plane::new(
+            -20. * Vec3::k(),
+            Vec3::k(),
+            Lambertian::new(Vec3::ones()),
+        ));
+        let bvh = Bvh::from(list);
+
+        assert_eq!(bvh.unbounded.len(), 1);
+        assert_eq!(bvh.objects.len(), 100);
+        assert!(bvh.bounding_box().is_none());
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 0.), -Vec3::k());
+        assert_eq!(bvh.hit(&ray, 0.001..f64::INFINITY).unwrap().t, 20.);
+    }
+
+    #[test]
+    fn nested_lists_are_flattened() {
+        let mut list = HitableList::new();
+        list.add(HitEnum::List(sphere_grid()));
+        list.add(HitEnum::List(sphere_grid()));
+        let bvh = Bvh::from(list);
+
+        assert_eq!(bvh.objects.len(), 200);
+    }
+
+    #[test]
+    fn hits_report_the_entry_they_came_from() {
+        let mut list = HitableList::new();
+        list.add(HitEnum::List(sphere_grid()));
+        list.add(Plane::new(
+            -20. * Vec3::k(),
+            Vec3::k(),
+            Lambertian::new(Vec3::ones()),
+        ));
+        let bvh = Bvh::from(list);
+
+        let sphere = Ray::new(Vec3::zeros(), -Vec3::k());
+        let plane = Ray::new(Vec3::new(0.5, 0.5, 0.), -Vec3::k());
+        assert_eq!(bvh.hit(&sphere, 0.001..f64::INFINITY).unwrap().object, 0);
+        assert_eq!(bvh.hit(&plane, 0.001..f64::INFINITY).unwrap().object, 1);
+    }
+
+    #[test]
+    fn empty_bvh_hits_nothing() {
+        let bvh = Bvh::from(HitableList::new());
+        let ray = Ray::new(Vec3::zeros(), -Vec3::k());
+
+        assert!(bvh.hit(&ray, 0.001..f64::INFINITY).is_none());
+        assert!(bvh.bounding_box().is_none());
+    }
+}
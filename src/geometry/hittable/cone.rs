@@ -0,0 +1,330 @@
+use super::{
+    Aabb, Boundary, HitEnum, HitRecord, Hittable, Interval, MaterialEnum, Point3, Ray, Solid, Vec3,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A cone standing on a disk of `base_radius` around `base`, narrowing to a point at `top`, or
+/// cut off there by a smaller disk when `top_radius` isn't zero. The ends are capped, so it's
+/// a closed solid.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ConeData", into = "ConeData")]
+pub struct Cone {
+    base: Point3,
+    top: Point3,
+    base_radius: f64,
+    top_radius: f64,
+    material: MaterialEnum,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConeData {
+    base: Point3,
+    top: Point3,
+    base_radius: f64,
+    #[serde(default)]
+    top_radius: f64,
+    material: MaterialEnum,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3,
+        top: Point3,
+        base_radius: f64,
+        top_radius: f64,
+        material: MaterialEnum,
+    ) -> HitEnum {
+        HitEnum::Cone(Self {
+            base,
+            top,
+            base_radius: base_radius.abs(),
+            top_radius: top_radius.abs(),
+            material,
+        })
+    }
+}
+
+impl TryFrom<ConeData> for Cone {
+    type Error = String;
+
+    fn try_from(data: ConeData) -> Result<Self, Self::Error> {
+        if (data.top - data.base).near_zero() {
+            return Err("a cone's base and top can't be in the same place".into());
+        }
+        for radius in [data.base_radius, data.top_radius] {
+            if !(radius >= 0. && radius.is_finite()) {
+                return Err("a cone's radii can't be negative or infinite".into());
+            }
+        }
+        Ok(Self {
+            base: data.base,
+            top: data.top,
+            base_radius: data.base_radius,
+            top_radius: data.top_radius,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Cone> for ConeData {
+    fn from(cone: Cone) -> Self {
+        Self {
+            base: cone.base,
+            top: cone.top,
+            base_radius: cone.base_radius,
+            top_radius: cone.top_radius,
+            material: cone.material,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let (t, normal) =
+            frustum_crossings(ray, self.base, self.top, self.base_radius, self.top_radius)
+                .into_iter()
+                .find(|(t, _)| t_range.contains(t))?;
+        Some(HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            normal,
+            self.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(frustum_bounds(
+            self.base,
+            self.top,
+            self.base_radius,
+            self.top_radius,
+        ))
+    }
+}
+
+impl Solid for Cone {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let crossings =
+            frustum_crossings(ray, self.base, self.top, self.base_radius, self.top_radius);
+        frustum_interval(&crossings, &self.material)
+    }
+}
+
+/// Everywhere along the ray's whole line it crosses the surface of a capped cone that narrows
+/// linearly from `base_radius` at `base` to `top_radius` at `top`, as `t` and the outward
+/// normal there, nearest first
+pub(super) fn frustum_crossings(
+    ray: &Ray,
+    base: Point3,
+    top: Point3,
+    base_radius: f64,
+    top_radius: f64,
+) -> Vec<(f64, Vec3)> {
+    let height = (top - base).length();
+    let axis = (top - base) / height;
+    // How much the radius grows for each unit up the axis
+    let slope = (top_radius - base_radius) / height;
+
+    // Split the ray into parts along and across the axis
+    let origin = ray.origin - base;
+    let (origin_up, direction_up) = (origin.dot(axis), ray.direction.dot(axis));
+    let origin_across = origin - origin_up * axis;
+    let direction_across = ray.direction - direction_up * axis;
+
+    let mut crossings = Vec::new();
+
+    // The side is where the distance from the axis equals the radius at that height
+    let radius_at_origin = base_radius + slope * origin_up;
+    let a = direction_across.length_squared() - slope * slope * direction_up * direction_up;
+    let half_b = origin_across.dot(direction_across) - slope * direction_up * radius_at_origin;
+    let c = origin_across.length_squared() - radius_at_origin * radius_at_origin;
+    let side_ts = if a.abs() < 1e-12 {
+        // Running parallel to the slanted side crosses it only once
+        if half_b.abs() < 1e-12 {
+            vec![]
+        } else {
+            vec![-c / (2. * half_b)]
+        }
+    } else {
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0. {
+            vec![]
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-half_b - root) / a, (-half_b + root) / a]
+        }
+    };
+    for t in side_ts {
+        let up = origin_up + t * direction_up;
+        if !(0.0..=height).contains(&up) {
+            continue;
+        }
+        let across = origin_across + t * direction_across;
+        let normal = if across.near_zero() {
+            axis
+        } else {
+            (across.unit_vector() - slope * axis).unit_vector()
+        };
+        crossings.push((t, normal));
+    }
+
+    // The caps
+    if direction_up.abs() > 1e-12 {
+        for (up, radius, normal) in [(0., base_radius, -axis), (height, top_radius, axis)] {
+            let t = (up - origin_up) / direction_up;
+            let across = origin_across + t * direction_across;
+            if radius > 0. && across.length_squared() <= radius * radius {
+                crossings.push((t, normal));
+            }
+        }
+    }
+
+    crossings.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    crossings
+}
+
+/// Cones are convex, so a ray is inside from where it first crosses the surface to where it last
+/// does
+pub(super) fn frustum_interval<'a>(
+    crossings: &[(f64, Vec3)],
+    material: &'a MaterialEnum,
+) -> Vec<Interval<'a>> {
+    match (crossings.first(), crossings.last()) {
+        (Some(&(enter, enter_normal)), Some(&(exit, exit_normal))) if crossings.len() > 1 => {
+            vec![Interval {
+                enter: Boundary {
+                    t: enter,
+                    outward_normal: enter_normal,
+                    material,
+                },
+                exit: Boundary {
+                    t: exit,
+                    outward_normal: exit_normal,
+                    material,
+                },
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The box around both end disks
+pub(super) fn frustum_bounds(base: Point3, top: Point3, base_radius: f64, top_radius: f64) -> Aabb {
+    let axis = (top - base).unit_vector();
+    let disk = |center: Point3, radius: f64| {
+        let reach = |component: f64| radius * (1. - component * component).max(0.).sqrt();
+        let extent = Vec3::new(reach(axis.x), reach(axis.y), reach(axis.z));
+        Aabb::new(center - extent, center + extent)
+    };
+    disk(base, base_radius).surrounding(disk(top, top_radius))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::material::Lambertian;
+
+    /// Radius one at `y = 0`, coming to a point at `y = 2`
+    fn cone() -> HitEnum {
+        Cone::new(
+            Vec3::zeros(),
+            2. * Vec3::j(),
+            1.,
+            0.,
+            Lambertian::new(Vec3::ones()),
+        )
+    }
+
+    #[test]
+    fn side_normals_lean_up_the_slope() {
+        let ray = Ray::new(Vec3::new(0., 1., 5.), -Vec3::k());
+
+        let record = cone().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 4.5).abs() < 1e-9);
+        assert_near(record.normal, Vec3::new(0., 1., 2.).unit_vector());
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn base_is_capped() {
+        let ray = Ray::new(Vec3::new(0.5, -3., 0.), Vec3::j());
+
+        let record = cone().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 3.).abs() < 1e-9);
+        assert_near(record.normal, -Vec3::j());
+    }
+
+    #[test]
+    fn grazing_rays_only_hit_inside_the_slope() {
+        // At height one the cone is half a unit across
+        let inside = Ray::new(Vec3::new(0.5 - 1e-6, 1., 5.), -Vec3::k());
+        let outside = Ray::new(Vec3::new(0.5 + 1e-6, 1., 5.), -Vec3::k());
+
+        assert!(cone().hit(&inside, 0.001..f64::INFINITY).is_some());
+        assert!(cone().hit(&outside, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rays_from_inside_hit_back_faces() {
+        let ray = Ray::new(Vec3::new(0., 0.5, 0.), Vec3::k());
+
+        let record = cone().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 0.75).abs() < 1e-9);
+        assert!(!record.front_face);
+    }
+
+    #[test]
+    fn truncated_cones_are_capped_at_both_ends() {
+        let frustum = Cone::new(
+            Vec3::zeros(),
+            2. * Vec3::j(),
+            1.,
+            0.5,
+            Lambertian::new(Vec3::ones()),
+        );
+        let ray = Ray::new(Vec3::new(0.2, 5., 0.), -Vec3::j());
+
+        let record = frustum.hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 3.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::j());
+    }
+
+    #[test]
+    fn bounding_box_holds_the_base() {
+        let bounds = cone().bounding_box().unwrap();
+
+        assert_near(bounds.min, Vec3::new(-1., 0., -1.));
+        assert_near(bounds.max, Vec3::new(1., 2., 1.));
+    }
+
+    #[test]
+    fn degenerate_cones_are_rejected() {
+        let cone = |top: &str, base_radius: f64, top_radius: f64| {
+            format!(
+                r#"{{ "Cone": {{
+                    "base": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+                    "top": {},
+                    "base_radius": {},
+                    "top_radius": {},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                top, base_radius, top_radius
+            )
+        };
+        let base = r#"{ "x": 0.0, "y": 0.0, "z": 0.0 }"#;
+        let up = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+
+        assert!(serde_json::from_str::<HitEnum>(&cone(base, 1., 0.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&cone(up, -1., 0.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&cone(up, 1., -0.5)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&cone(up, 1., 0.5)).is_ok());
+    }
+}
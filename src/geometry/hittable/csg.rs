@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Aabb, HitEnum, HitRecord, Hittable, Interval, Ray, Solid};
+
+/// Two solids combined into one by constructive solid geometry. Only the surface of the result
+/// can be hit, and each part of it keeps the material of the solid it came from, so the walls
+/// of a hole cut by `Difference` take the material of the solid that was cut away.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "CsgData", into = "CsgData")]
+pub struct Csg {
+    data: CsgData,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CsgData {
+    operation: Operation,
+    left: Arc<HitEnum>,
+    right: Arc<HitEnum>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operation {
+    /// Inside either solid
+    Union,
+    /// Inside both solids
+    Intersection,
+    /// Inside the left solid but not the right one
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Self::Union => in_left || in_right,
+            Self::Intersection => in_left && in_right,
+            Self::Difference => in_left && !in_right,
+        }
+    }
+}
+
+impl Csg {
+    /// Combine `left` and `right`, or `None` unless both are solid
+    pub fn new(
+        operation: Operation,
+        left: impl Into<Arc<HitEnum>>,
+        right: impl Into<Arc<HitEnum>>,
+    ) -> Option<HitEnum> {
+        let data = CsgData {
+            operation,
+            left: left.into(),
+            right: right.into(),
+        };
+        Self::try_from(data).ok().map(HitEnum::Csg)
+    }
+
+    pub fn union(left: impl Into<Arc<HitEnum>>, right: impl Into<Arc<HitEnum>>) -> Option<HitEnum> {
+        Self::new(Operation::Union, left, right)
+    }
+
+    pub fn intersection(
+        left: impl Into<Arc<HitEnum>>,
+        right: impl Into<Arc<HitEnum>>,
+    ) -> Option<HitEnum> {
+        Self::new(Operation::Intersection, left, right)
+    }
+
+    /// `left` with `right` cut out of it
+    pub fn difference(
+        left: impl Into<Arc<HitEnum>>,
+        right: impl Into<Arc<HitEnum>>,
+    ) -> Option<HitEnum> {
+        Self::new(Operation::Difference, left, right)
+    }
+}
+
+impl TryFrom<CsgData> for Csg {
+    type Error = String;
+
+    fn try_from(data: CsgData) -> Result<Self, Self::Error> {
+        // Quadrics are left out even when bounded: their bounds cut most of them open, and an
+        // open surface has no inside to combine. An ellipsoid is a sphere scaled by a transform.
+        if !data.left.is_solid() || !data.right.is_solid() {
+            return Err(
+                "CSG can only combine spheres, cuboids, cylinders, cones and tori, \
+                 or transforms and combinations of them. Quadrics can't be combined, \
+                 so make ellipsoids by scaling a sphere."
+                    .into(),
+            );
+        }
+        Ok(Self { data })
+    }
+}
+
+impl Csg {
+    pub(super) fn unresolved_names(&self) -> Vec<&str> {
+        let mut names = self.data.left.unresolved_names();
+        names.extend(self.data.right.unresolved_names());
+        names
+    }
+
+    /// Look up the names placed in either solid among `objects`, checking again that what they
+    /// turned out to be is solid
+    pub(super) fn resolve(self, objects: &BTreeMap<String, Arc<HitEnum>>) -> Result<Self, String> {
+        let resolve = |object: Arc<HitEnum>| {
+            Ok::<_, String>(Arc::new(Arc::unwrap_or_clone(object).resolve(objects)?))
+        };
+        Self::try_from(CsgData {
+            operation: self.data.operation,
+            left: resolve(self.data.left)?,
+            right: resolve(self.data.right)?,
+        })
+    }
+}
+
+impl From<Csg> for CsgData {
+    fn from(csg: Csg) -> Self {
+        csg.data
+    }
+}
+
+impl Solid for Csg {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let left = self.data.left.intervals(ray);
+        let right = self.data.right.intervals(ray);
+        if right.is_empty() && self.data.operation != Operation::Intersection {
+            return left;
+        }
+        if left.is_empty() && self.data.operation == Operation::Union {
+            return right;
+        }
+
+        // Sweep along the ray through every boundary of either side, keeping track of which
+        // sides it's in, and note where that takes it into or out of the combination
+        let mut boundaries = Vec::with_capacity(2 * (left.len() + right.len()));
+        for (side, intervals) in [(0, &left), (1, &right)] {
+            for interval in intervals {
+                boundaries.push((side, true, interval.enter));
+                boundaries.push((side, false, interval.exit));
+            }
+        }
+        boundaries.sort_by(|(_, _, a), (_, _, b)| a.t.total_cmp(&b.t));
+
+        let mut inside = [false, false];
+        let mut combined = Vec::new();
+        let mut enter = None;
+        for (side, entering, mut boundary) in boundaries {
+            let was_inside = self.data.operation.contains(inside[0], inside[1]);
+            inside[side] = entering;
+            let is_inside = self.data.operation.contains(inside[0], inside[1]);
+
+            // Going into the cut-away solid is coming out of what's left, and the other way round
+            if side == 1 && self.data.operation == Operation::Difference {
+                boundary.outward_normal = -boundary.outward_normal;
+            }
+            match (was_inside, is_inside) {
+                (false, true) => enter = Some(boundary),
+                (true, false) => {
+                    if let Some(enter) = enter.take() {
+                        combined.push(Interval {
+                            enter,
+                            exit: boundary,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        combined
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let boundary = self
+            .intervals(ray)
+            .into_iter()
+            .flat_map(|interval| [interval.enter, interval.exit])
+            .find(|boundary| t_range.contains(&boundary.t))?;
+        Some(boundary.record(ray))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let left = self.data.left.bounding_box()?;
+        let right = self.data.right.bounding_box()?;
+        Some(match self.data.operation {
+            Operation::Union => left.surrounding(right),
+            Operation::Intersection => {
+                // Empty overlaps are squashed to a point, since nothing in them can be hit
+                let min = left.min.max(right.min);
+                Aabb::new(min, min.max(left.max.min(right.max)))
+            }
+            Operation::Difference => left,
+        })
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        let mut files = self.data.left.source_files();
+        files.extend(self.data.right.source_files());
+        files
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::geometry::{Cuboid, Cylinder, Matrix4, Plane, Quadric, Sphere, Transform, Vec3};
+    use crate::material::{Lambertian, Material, MaterialEnum};
+
+    fn paint(red: f64) -> MaterialEnum {
+        Lambertian::new(Vec3::new(red, 0., 0.))
+    }
+
+    /// Which of the `paint`s was hit
+    fn red(record: &HitRecord) -> f64 {
+        record.material.albedo().x
+    }
+
+    fn assert_intervals(intervals: Vec<Interval>, expected: &[(f64, f64)]) {
+        let found = intervals
+            .iter()
+            .map(|interval| (interval.enter.t, interval.exit.t))
+            .collect::<Vec<_>>();
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for ((enter, exit), (expected_enter, expected_exit)) in found.iter().zip(expected) {
+            assert!(
+                (enter - expected_enter).abs() < 1e-9 && (exit - expected_exit).abs() < 1e-9,
+                "{:?}",
+                found
+            );
+        }
+    }
+
+    /// Two unit spheres overlapping by one unit along x
+    fn spheres(operation: Operation) -> HitEnum {
+        Csg::new(
+            operation,
+            Sphere::new(Vec3::new(-0.5, 0., 0.), 1., paint(0.25)),
+            Sphere::new(Vec3::new(0.5, 0., 0.), 1., paint(0.75)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn union_has_no_surface_inside() {
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::i());
+        let union = spheres(Operation::Union);
+
+        assert_intervals(union.intervals(&ray), &[(3.5, 6.5)]);
+
+        // From inside the overlap the ray carries on to the far side of the right sphere
+        let ray = Ray::new(Vec3::zeros(), Vec3::i());
+        let record = union.hit(&ray, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 1.5).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert_eq!(red(&record), 0.75);
+    }
+
+    #[test]
+    fn intersection_is_bounded_by_the_other_solid() {
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::i());
+        let lens = spheres(Operation::Intersection);
+
+        let record = lens.hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        // Entering the lens means going into the right sphere
+        assert!((record.t - 4.5).abs() < 1e-9);
+        assert_near(record.normal, -Vec3::i());
+        assert_eq!(red(&record), 0.75);
+        assert_intervals(lens.intervals(&ray), &[(4.5, 5.5)]);
+    }
+
+    #[test]
+    fn difference_faces_into_the_bite() {
+        let ray = Ray::new(Vec3::new(5., 0., 0.), -Vec3::i());
+        let bitten = spheres(Operation::Difference);
+
+        let record = bitten.hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        // The bitten face belongs to the right sphere, facing back the way it came in
+        assert!((record.t - 5.5).abs() < 1e-9);
+        assert_near(record.normal, Vec3::i());
+        assert!(record.front_face);
+        assert_eq!(red(&record), 0.75);
+    }
+
+    #[test]
+    fn sphere_with_a_hole_through_it() {
+        let drilled = Csg::difference(
+            Sphere::new(Vec3::zeros(), 1., paint(0.25)),
+            Cylinder::new(-2. * Vec3::j(), 2. * Vec3::j(), 0.5, paint(0.75)),
+        )
+        .unwrap();
+
+        // Straight down the hole
+        let ray = Ray::new(5. * Vec3::j(), -Vec3::j());
+        assert!(drilled.hit(&ray, 0.001..f64::INFINITY).is_none());
+
+        // Across the middle, the ray goes in and out of the hole
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::i());
+        assert_intervals(drilled.intervals(&ray), &[(4., 4.5), (5.5, 6.)]);
+        let record = drilled.hit(&ray, 4.1..f64::INFINITY).unwrap();
+        assert!((record.t - 4.5).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert_eq!(red(&record), 0.75);
+
+        // From inside the hole the wall faces the ray
+        let ray = Ray::new(Vec3::zeros(), Vec3::k());
+        let record = drilled.hit(&ray, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 0.5).abs() < 1e-9);
+        assert!(record.front_face);
+        assert_near(record.normal, -Vec3::k());
+    }
+
+    #[test]
+    fn combinations_nest_and_move() {
+        // A box with a spherical dimple, moved back along z and combined again
+        let dimpled = Csg::difference(
+            Cuboid::new(-Vec3::ones(), Vec3::ones(), paint(0.25)),
+            Sphere::new(Vec3::k(), 0.5, paint(0.75)),
+        )
+        .unwrap();
+        let moved = Transform::new(dimpled, Matrix4::translation(-5. * Vec3::k())).unwrap();
+        let joined = Csg::union(moved, Sphere::new(Vec3::zeros(), 0.5, paint(0.5))).unwrap();
+
+        let ray = Ray::new(Vec3::zeros(), -Vec3::k());
+        assert_intervals(joined.intervals(&ray), &[(-0.5, 0.5), (4.5, 6.)]);
+        let record = joined.hit(&ray, 1.0..f64::INFINITY).unwrap();
+        assert!((record.t - 4.5).abs() < 1e-9);
+        assert_near(record.normal, Vec3::k());
+        assert_eq!(red(&record), 0.75);
+    }
+
+    #[test]
+    fn only_solids_can_be_combined() {
+        let plane = Plane::new(Vec3::zeros(), Vec3::j(), paint(0.5));
+        let sphere = Sphere::new(Vec3::zeros(), 1., paint(0.5));
+
+        assert!(Csg::union(plane.clone(), sphere.clone()).is_none());
+        let moved_plane = Transform::new(plane, Matrix4::translation(Vec3::i())).unwrap();
+        assert!(Csg::intersection(sphere, moved_plane).is_none());
+    }
+
+    #[test]
+    fn quadrics_are_rejected_even_when_closed() {
+        let ellipsoid = Quadric::ellipsoid(Vec3::zeros(), Vec3::new(2., 1., 1.), paint(0.5));
+        let sphere = Sphere::new(Vec3::zeros(), 1., paint(0.5));
+
+        let err = Csg::try_from(CsgData {
+            operation: Operation::Union,
+            left: Arc::new(ellipsoid),
+            right: Arc::new(sphere),
+        })
+        .err()
+        .unwrap();
+
+        assert!(err.contains("Quadrics can't be combined"), "{}", err);
+    }
+
+    #[test]
+    fn combinations_are_read_from_scene_files() {
+        let sphere = |x: f64| {
+            format!(
+                r#"{{ "Sphere": {{
+                    "center": {{ "x": {}, "y": 0.0, "z": 0.0 }},
+                    "radius": 1.0,
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                x
+            )
+        };
+        let json = format!(
+            r#"{{ "Csg": {{ "operation": "Intersection", "left": {}, "right": {} }} }}"#,
+            sphere(-0.5),
+            sphere(0.5)
+        );
+        let lens: HitEnum = serde_json::from_str(&json).unwrap();
+
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::i());
+        assert!((lens.hit(&ray, 0.001..f64::INFINITY).unwrap().t - 4.5).abs() < 1e-9);
+        let reloaded: HitEnum =
+            serde_json::from_str(&serde_json::to_string(&lens).unwrap()).unwrap();
+        assert!((reloaded.hit(&ray, 0.001..f64::INFINITY).unwrap().t - 4.5).abs() < 1e-9);
+
+        let bounds = lens.bounding_box().unwrap();
+        assert_near(bounds.min, Vec3::new(-0.5, -1., -1.));
+        assert_near(bounds.max, Vec3::new(0.5, 1., 1.));
+    }
+}
@@ -0,0 +1,184 @@
+use super::hitable_list::HitableList;
+use super::quad::Quad;
+use super::{
+    Aabb, Boundary, HitEnum, HitRecord, Hittable, Interval, MaterialEnum, Point3, Ray, Solid, Vec3,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// An axis-aligned box made of six quads, each facing out. Serializes as its two corners and
+/// material.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "CuboidData", into = "CuboidData")]
+pub struct Cuboid {
+    data: CuboidData,
+    sides: HitableList,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CuboidData {
+    /// Any two opposite corners
+    min: Point3,
+    max: Point3,
+    material: MaterialEnum,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: MaterialEnum) -> HitEnum {
+        HitEnum::Cuboid(Self::from(CuboidData {
+            min: a.min(b),
+            max: a.max(b),
+            material,
+        }))
+    }
+}
+
+impl From<CuboidData> for Cuboid {
+    fn from(data: CuboidData) -> Self {
+        let (min, max) = (data.min.min(data.max), data.min.max(data.max));
+        let extent = max - min;
+        let dx = extent.x * Vec3::i();
+        let dy = extent.y * Vec3::j();
+        let dz = extent.z * Vec3::k();
+
+        // Each side's edges are picked so `u × v` points out of the box
+        let mut sides = HitableList::new();
+        for (corner, u, v) in [
+            (Vec3::new(min.x, min.y, max.z), dx, dy),
+            (Vec3::new(max.x, min.y, min.z), -dx, dy),
+            (Vec3::new(max.x, min.y, max.z), -dz, dy),
+            (min, dz, dy),
+            (Vec3::new(min.x, max.y, max.z), dx, -dz),
+            (min, dx, dz),
+        ] {
+            sides.add(Quad::new(corner, u, v, data.material.clone()));
+        }
+        Self { data, sides }
+    }
+}
+
+impl From<Cuboid> for CuboidData {
+    fn from(cuboid: Cuboid) -> Self {
+        cuboid.data
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        self.sides.hit(ray, t_range)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.data.min, self.data.max))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        self.sides.lights()
+    }
+}
+
+impl Solid for Cuboid {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        // The slab test, remembering which side each slab was crossed at
+        let Aabb { min, max } = Aabb::new(self.data.min, self.data.max);
+        let (mut enter, mut exit) = (
+            (f64::NEG_INFINITY, Vec3::zeros()),
+            (f64::INFINITY, Vec3::zeros()),
+        );
+        for (axis, normal) in [Vec3::i(), Vec3::j(), Vec3::k()].into_iter().enumerate() {
+            let (origin, direction) = (ray.origin[axis], ray.direction[axis]);
+            if direction == 0. {
+                if origin < min[axis] || origin > max[axis] {
+                    return Vec::new();
+                }
+                continue;
+            }
+            let mut near = ((min[axis] - origin) / direction, -normal);
+            let mut far = ((max[axis] - origin) / direction, normal);
+            if direction < 0. {
+                std::mem::swap(&mut near, &mut far);
+            }
+            if near.0 > enter.0 {
+                enter = near;
+            }
+            if far.0 < exit.0 {
+                exit = far;
+            }
+        }
+        if exit.0 < enter.0 {
+            return Vec::new();
+        }
+        let boundary = |(t, outward_normal)| Boundary {
+            t,
+            outward_normal,
+            material: &self.data.material,
+        };
+        vec![Interval {
+            enter: boundary(enter),
+            exit: boundary(exit),
+        }]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::{DiffuseLight, Lambertian};
+    use pretty_assertions::assert_eq;
+
+    fn cuboid(material: MaterialEnum) -> HitEnum {
+        Cuboid::new(Vec3::new(1., 2., 3.), Vec3::new(-1., -2., -3.), material)
+    }
+
+    #[test]
+    fn every_side_faces_out() {
+        let cuboid = cuboid(Lambertian::new(Vec3::ones()));
+        for axis in [Vec3::i(), Vec3::j(), Vec3::k()] {
+            for side in [axis, -axis] {
+                let ray = Ray::new(10. * side, -side);
+
+                let record = cuboid.hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+                assert_eq!(record.normal, side);
+                assert!(record.front_face);
+            }
+        }
+    }
+
+    #[test]
+    fn rays_from_inside_hit_back_faces() {
+        let ray = Ray::new(Vec3::zeros(), Vec3::j());
+
+        let record = cuboid(Lambertian::new(Vec3::ones()))
+            .hit(&ray, 0.0..f64::INFINITY)
+            .unwrap();
+
+        assert_eq!(record.t, 2.);
+        assert!(!record.front_face);
+    }
+
+    #[test]
+    fn glowing_boxes_light_the_scene_from_every_side() {
+        assert_eq!(cuboid(DiffuseLight::new(Vec3::ones())).lights().len(), 6);
+        assert!(cuboid(Lambertian::new(Vec3::ones())).lights().is_empty());
+    }
+
+    #[test]
+    fn corners_are_sorted_when_read() {
+        let json = r#"{ "Cuboid": {
+            "min": { "x": 1.0, "y": 1.0, "z": 1.0 },
+            "max": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "material": { "Lambertian": { "albedo": { "x": 1.0, "y": 1.0, "z": 1.0 } } }
+        } }"#;
+        let cuboid: HitEnum = serde_json::from_str(json).unwrap();
+
+        let bounds = cuboid.bounding_box().unwrap();
+        assert_eq!(bounds.min, Vec3::zeros());
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 5.), -Vec3::k());
+        assert_eq!(
+            cuboid.hit(&ray, 0.0..f64::INFINITY).unwrap().normal,
+            Vec3::k()
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use super::cone::{frustum_bounds, frustum_crossings, frustum_interval};
+use super::{Aabb, HitEnum, HitRecord, Hittable, Interval, MaterialEnum, Point3, Ray, Solid};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A cylinder running from the center of its bottom cap at `base` to that of its top cap at
+/// `top`. Both ends are capped, so it's a closed solid.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "CylinderData", into = "CylinderData")]
+pub struct Cylinder {
+    base: Point3,
+    top: Point3,
+    radius: f64,
+    material: MaterialEnum,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CylinderData {
+    base: Point3,
+    top: Point3,
+    radius: f64,
+    material: MaterialEnum,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: f64, material: MaterialEnum) -> HitEnum {
+        HitEnum::Cylinder(Self {
+            base,
+            top,
+            radius: radius.abs(),
+            material,
+        })
+    }
+}
+
+impl TryFrom<CylinderData> for Cylinder {
+    type Error = String;
+
+    fn try_from(data: CylinderData) -> Result<Self, Self::Error> {
+        if (data.top - data.base).near_zero() {
+            return Err("a cylinder's base and top can't be in the same place".into());
+        }
+        if !(data.radius >= 0. && data.radius.is_finite()) {
+            return Err("a cylinder's radius can't be negative or infinite".into());
+        }
+        Ok(Self {
+            base: data.base,
+            top: data.top,
+            radius: data.radius,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Cylinder> for CylinderData {
+    fn from(cylinder: Cylinder) -> Self {
+        Self {
+            base: cylinder.base,
+            top: cylinder.top,
+            radius: cylinder.radius,
+            material: cylinder.material,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let (t, normal) = frustum_crossings(ray, self.base, self.top, self.radius, self.radius)
+            .into_iter()
+            .find(|(t, _)| t_range.contains(t))?;
+        Some(HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            normal,
+            self.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(frustum_bounds(
+            self.base,
+            self.top,
+            self.radius,
+            self.radius,
+        ))
+    }
+}
+
+impl Solid for Cylinder {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let crossings = frustum_crossings(ray, self.base, self.top, self.radius, self.radius);
+        frustum_interval(&crossings, &self.material)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::geometry::Vec3;
+    use crate::material::Lambertian;
+
+    /// Radius one, from `z = -1` to `z = 1`
+    fn cylinder() -> HitEnum {
+        Cylinder::new(-Vec3::k(), Vec3::k(), 1., Lambertian::new(Vec3::ones()))
+    }
+
+    #[test]
+    fn side_is_hit_with_outward_normal() {
+        let ray = Ray::new(Vec3::new(5., 0., 0.5), -Vec3::i());
+
+        let record = cylinder().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 4.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::i());
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn caps_close_the_ends() {
+        let ray = Ray::new(Vec3::new(0.3, 0.3, 5.), -Vec3::k());
+
+        let record = cylinder().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 4.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::k());
+    }
+
+    #[test]
+    fn grazing_rays_only_hit_inside_the_radius() {
+        let inside = Ray::new(Vec3::new(5., 1. - 1e-6, 0.), -Vec3::i());
+        let outside = Ray::new(Vec3::new(5., 1. + 1e-6, 0.), -Vec3::i());
+
+        assert!(cylinder().hit(&inside, 0.001..f64::INFINITY).is_some());
+        assert!(cylinder().hit(&outside, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rays_past_the_ends_miss() {
+        let ray = Ray::new(Vec3::new(5., 0., 1.01), -Vec3::i());
+
+        assert!(cylinder().hit(&ray, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rays_from_inside_hit_back_faces() {
+        for direction in [Vec3::i(), Vec3::k()] {
+            let ray = Ray::new(Vec3::zeros(), direction);
+
+            let record = cylinder().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+            assert!((record.t - 1.).abs() < 1e-9);
+            assert!(!record.front_face);
+            assert_near(record.normal, -direction);
+        }
+    }
+
+    #[test]
+    fn slanted_cylinders_are_bounded_by_their_caps() {
+        let slanted = Cylinder::new(
+            Vec3::zeros(),
+            Vec3::new(1., 1., 0.),
+            1.,
+            Lambertian::new(Vec3::ones()),
+        );
+        let reach = 0.5f64.sqrt();
+
+        let bounds = slanted.bounding_box().unwrap();
+
+        assert_near(bounds.min, Vec3::new(-reach, -reach, -1.));
+        assert_near(bounds.max, Vec3::new(1. + reach, 1. + reach, 1.));
+    }
+
+    #[test]
+    fn degenerate_cylinders_are_rejected() {
+        let cylinder = |top: &str, radius: f64| {
+            format!(
+                r#"{{ "Cylinder": {{
+                    "base": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+                    "top": {},
+                    "radius": {},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                top, radius
+            )
+        };
+        let base = r#"{ "x": 0.0, "y": 0.0, "z": 0.0 }"#;
+        let up = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+
+        assert!(serde_json::from_str::<HitEnum>(&cylinder(base, 1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&cylinder(up, -1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&cylinder(up, 1.)).is_ok());
+    }
+}
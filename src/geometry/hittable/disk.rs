@@ -0,0 +1,237 @@
+use std::f64::consts::PI;
+use std::ops::Range;
+
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Ray, Sampleable, Vec3};
+use crate::sampler::Sampler;
+use serde::{Deserialize, Serialize};
+
+/// A flat circle facing along `normal`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "DiskData", into = "DiskData")]
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    material: MaterialEnum,
+}
+
+/// How a disk is written in scene files, where the normal needn't be of unit length
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskData {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    material: MaterialEnum,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: MaterialEnum) -> HitEnum {
+        HitEnum::Disk(Self {
+            center,
+            normal: normal.unit_vector(),
+            radius,
+            material,
+        })
+    }
+
+    fn intersect(&self, ray: &Ray, t_range: Range<f64>) -> Option<f64> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        let inside = (ray.at(t) - self.center).length_squared() <= self.radius * self.radius;
+        (t_range.contains(&t) && inside).then_some(t)
+    }
+}
+
+impl TryFrom<DiskData> for Disk {
+    type Error = String;
+
+    fn try_from(data: DiskData) -> Result<Self, Self::Error> {
+        if data.normal.near_zero() {
+            return Err("a disk's normal can't be zero".into());
+        }
+        if data.radius < 0. {
+            return Err("a disk's radius can't be negative".into());
+        }
+        Ok(Self {
+            center: data.center,
+            normal: data.normal.unit_vector(),
+            radius: data.radius,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Disk> for DiskData {
+    fn from(disk: Disk) -> Self {
+        Self {
+            center: disk.center,
+            normal: disk.normal,
+            radius: disk.radius,
+            material: disk.material,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let t = self.intersect(ray, t_range)?;
+        Some(HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            self.normal,
+            self.material.clone(),
+        ))
+    }
+
+    /// Along each axis the rim reaches out by the radius times the sine of its tilt from it
+    fn bounding_box(&self) -> Option<Aabb> {
+        let reach = |component: f64| self.radius * (1. - component * component).max(0.).sqrt();
+        let extent = Vec3::new(
+            reach(self.normal.x),
+            reach(self.normal.y),
+            reach(self.normal.z),
+        );
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        if self.material.is_emissive() {
+            vec![HitEnum::Disk(self.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Sampleable for Disk {
+    /// Directions toward points spread evenly over the disk's area
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        let [a, b] = sampler.get_2d();
+        let distance = self.radius * a.sqrt();
+        let angle = 2. * PI * b;
+        let (x, y) = self.normal.perpendiculars();
+        let point = self.center + distance * (angle.cos() * x + angle.sin() * y);
+        (point - origin).unit_vector()
+    }
+
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let t = match self.intersect(&ray, 0.001..f64::INFINITY) {
+            Some(t) => t,
+            None => return 0.,
+        };
+        let cosine = self.normal.dot(ray.direction).abs();
+        if cosine < 1e-12 {
+            return 0.;
+        }
+        t * t / (cosine * PI * self.radius * self.radius)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::{Bvh, HitableList};
+    use crate::material::DiffuseLight;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    /// Radius one, tilted to face back toward the origin from `z = -2`
+    fn disk() -> Disk {
+        match Disk::new(
+            -2. * Vec3::k(),
+            Vec3::new(0., 1., 1.),
+            1.,
+            DiffuseLight::new(Vec3::ones()),
+        ) {
+            HitEnum::Disk(disk) => disk,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn hits_inside_the_rim_only() {
+        let center = Ray::new(Vec3::zeros(), -Vec3::k());
+        let past_rim = Ray::new(Vec3::new(1.01, 0., 0.), -Vec3::k());
+
+        let record = disk().hit(&center, 0.0..f64::INFINITY).unwrap();
+        assert!((record.t - 2.).abs() < 1e-12);
+        assert!(record.front_face);
+        assert!(disk().hit(&past_rim, 0.0..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn bounding_box_fits_the_tilted_rim() {
+        let bounds = disk().bounding_box().unwrap();
+        let tilt = 0.5f64.sqrt();
+
+        assert!((bounds.max - Vec3::new(1., tilt, -2. + tilt)).length() < 1e-12);
+        assert!((bounds.min - Vec3::new(-1., -tilt, -2. - tilt)).length() < 1e-12);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let mut rng = Pcg64Mcg::seed_from_u64(2);
+        let samples = 200_000;
+        let total = (0..samples)
+            .map(|_| disk().direction_pdf(Vec3::zeros(), Vec3::random_unit_vector(&mut rng)))
+            .sum::<f64>();
+
+        let integral = total * 4. * PI / samples as f64;
+        assert!((integral - 1.).abs() < 0.02, "{}", integral);
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_disk() {
+        let mut rng = Pcg64Mcg::seed_from_u64(3);
+        for _ in 0..1000 {
+            let direction = disk().sample_direction(Vec3::zeros(), &mut rng);
+
+            assert!(disk().direction_pdf(Vec3::zeros(), direction) > 0.);
+        }
+    }
+
+    #[test]
+    fn normals_are_normalized_when_read() {
+        let json = r#"{ "Disk": {
+            "center": { "x": 0.0, "y": 0.0, "z": -2.0 },
+            "normal": { "x": 0.0, "y": 1.0, "z": 1.0 },
+            "radius": 1.0,
+            "material": { "Lambertian": { "albedo": { "x": 1.0, "y": 1.0, "z": 1.0 } } }
+        } }"#;
+        let mut list = HitableList::new();
+        list.add(serde_json::from_str(json).unwrap());
+        let bvh = Bvh::new(list);
+
+        let ray = Ray::new(Vec3::new(0.5, 0., 0.), -Vec3::k());
+        let record = bvh.hit(&ray, 0.0..f64::INFINITY).unwrap();
+        assert!((record.t - 2.).abs() < 1e-12);
+        assert!((record.normal.length() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degenerate_disks_are_rejected() {
+        let disk = |normal: &str, radius: f64| {
+            format!(
+                r#"{{ "Disk": {{
+                    "center": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+                    "normal": {},
+                    "radius": {},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                normal, radius
+            )
+        };
+        let zero = r#"{ "x": 0.0, "y": 0.0, "z": 0.0 }"#;
+        let up = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+
+        assert!(serde_json::from_str::<HitEnum>(&disk(zero, 1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&disk(up, -1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&disk(up, 1.)).is_ok());
+    }
+}
@@ -1,22 +1,42 @@
 use super::HitEnum;
 use super::Ray;
-use super::{HitRecord, Hittable};
+use super::{Aabb, HitRecord, Hittable};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub struct HitableList {
     list: Vec<HitEnum>,
 }
 
 impl HitableList {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self { list: Vec::new() }
     }
 
     pub fn add(&mut self, hittable: HitEnum) {
         self.list.push(hittable);
     }
+
+    pub(crate) fn unresolved_names(&self) -> Vec<&str> {
+        self.list
+            .iter()
+            .flat_map(HitEnum::unresolved_names)
+            .collect()
+    }
+
+    /// Look up every name placed in the list among `objects`
+    pub(crate) fn resolve(self, objects: &BTreeMap<String, Arc<HitEnum>>) -> Result<Self, String> {
+        let list = self
+            .list
+            .into_iter()
+            .map(|object| object.resolve(objects))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { list })
+    }
 }
 
 impl Hittable for HitableList {
@@ -31,4 +51,27 @@ impl Hittable for HitableList {
                     .unwrap_or(std::cmp::Ordering::Equal)
             })
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.list.iter().map(Hittable::bounding_box);
+        let first = boxes.next()??;
+        boxes.try_fold(first, |acc, bounds| Some(acc.surrounding(bounds?)))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        self.list.iter().flat_map(Hittable::lights).collect()
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        self.list.iter().flat_map(Hittable::source_files).collect()
+    }
+}
+
+impl IntoIterator for HitableList {
+    type Item = HitEnum;
+    type IntoIter = std::vec::IntoIter<HitEnum>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
 }
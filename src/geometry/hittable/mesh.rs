@@ -0,0 +1,234 @@
+use super::bvh::BvhTree;
+use super::triangle;
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Ray, Vec3};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A triangle mesh sharing one vertex buffer and material between all of its faces
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "MeshData", into = "MeshData")]
+pub struct Mesh {
+    vertices: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    /// Vertex indices of each face, kept in the order the tree's leaves refer to them
+    faces: Vec<[usize; 3]>,
+    material: MaterialEnum,
+    tree: BvhTree,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshData {
+    vertices: Vec<Point3>,
+    /// One normal per vertex, for smooth shading
+    #[serde(default)]
+    normals: Option<Vec<Vec3>>,
+    faces: Vec<[usize; 3]>,
+    material: MaterialEnum,
+}
+
+impl Mesh {
+    /// Panics if a face refers to a vertex that doesn't exist, or if there isn't exactly one
+    /// normal per vertex.
+    pub fn new(
+        vertices: Vec<Point3>,
+        normals: Option<Vec<Vec3>>,
+        faces: Vec<[usize; 3]>,
+        material: MaterialEnum,
+    ) -> HitEnum {
+        let data = MeshData {
+            vertices,
+            normals,
+            faces,
+            material,
+        };
+        HitEnum::Mesh(Self::try_from(data).unwrap())
+    }
+
+    fn face(&self, face: usize) -> [Point3; 3] {
+        self.faces[face].map(|index| self.vertices[index])
+    }
+}
+
+impl TryFrom<MeshData> for Mesh {
+    type Error = String;
+
+    fn try_from(data: MeshData) -> Result<Self, Self::Error> {
+        if let Some(index) = data
+            .faces
+            .iter()
+            .flatten()
+            .find(|&&index| index >= data.vertices.len())
+        {
+            return Err(format!(
+                "Mesh face refers to vertex {} but there are only {} vertices",
+                index,
+                data.vertices.len()
+            ));
+        }
+        if let Some(normals) = &data.normals {
+            if normals.len() != data.vertices.len() {
+                return Err(format!(
+                    "Mesh has {} vertices but {} normals",
+                    data.vertices.len(),
+                    normals.len()
+                ));
+            }
+        }
+
+        let bounds = data
+            .faces
+            .iter()
+            .map(|face| triangle::bounds(&face.map(|index| data.vertices[index])))
+            .collect::<Vec<_>>();
+        let (tree, order) = BvhTree::build(&bounds);
+        let faces = order.into_iter().map(|face| data.faces[face]).collect();
+
+        Ok(Self {
+            vertices: data.vertices,
+            normals: data
+                .normals
+                .map(|normals| normals.into_iter().map(Vec3::unit_vector).collect()),
+            faces,
+            material: data.material,
+            tree,
+        })
+    }
+}
+
+impl From<Mesh> for MeshData {
+    fn from(mesh: Mesh) -> Self {
+        Self {
+            vertices: mesh.vertices,
+            normals: mesh.normals,
+            faces: mesh.faces,
+            material: mesh.material,
+        }
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let (face, t, u, v) = self.tree.hit(ray, t_range, |face, t_range| {
+            let (t, u, v) = triangle::intersect(ray, &self.face(face), t_range)?;
+            Some((t, (face, t, u, v)))
+        })?;
+
+        let normals = self
+            .normals
+            .as_ref()
+            .map(|normals| self.faces[face].map(|index| normals[index]));
+        Some(triangle::hit_record(
+            ray,
+            t,
+            u,
+            v,
+            &self.face(face),
+            normals.as_ref(),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.tree.bounds()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::hittable::triangle::Triangle;
+    use crate::material::Lambertian;
+    use pretty_assertions::assert_eq;
+
+    /// A unit cube centered on the origin, as two triangles per side
+    fn cube() -> (Vec<Point3>, Vec<[usize; 3]>) {
+        let vertices = (0..8)
+            .map(|corner| {
+                Vec3::new(
+                    (corner & 1) as f64 - 0.5,
+                    ((corner >> 1) & 1) as f64 - 0.5,
+                    ((corner >> 2) & 1) as f64 - 0.5,
+                )
+            })
+            .collect();
+        let faces = vec![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+        (vertices, faces)
+    }
+
+    #[test]
+    fn mesh_matches_separate_triangles() {
+        let (vertices, faces) = cube();
+        let material = Lambertian::new(Vec3::ones());
+        let triangles = faces
+            .iter()
+            .map(|face| Triangle::new(face.map(|i| vertices[i]), None, material.clone()))
+            .collect::<Vec<_>>();
+        let mesh = Mesh::new(vertices, None, faces, material);
+
+        for x in -10..=10 {
+            for y in -10..=10 {
+                let direction = Vec3::new(x as f64 * 0.07, y as f64 * 0.07, -1.);
+                let ray = Ray::new(Vec3::new(0.05, 0.1, 3.), direction);
+                let expected = triangles
+                    .iter()
+                    .filter_map(|triangle| triangle.hit(&ray, 0.001..f64::INFINITY))
+                    .map(|record| (record.t, record.normal))
+                    .min_by(|lhs, rhs| lhs.0.partial_cmp(&rhs.0).unwrap());
+                let actual = mesh
+                    .hit(&ray, 0.001..f64::INFINITY)
+                    .map(|record| (record.t, record.normal));
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_bounds_cover_all_vertices() {
+        let (vertices, faces) = cube();
+        let mesh = Mesh::new(vertices, None, faces, Lambertian::new(Vec3::ones()));
+
+        let expected = Aabb::new(-0.5 * Vec3::ones(), 0.5 * Vec3::ones());
+        assert_eq!(mesh.bounding_box(), Some(expected));
+    }
+
+    #[test]
+    fn out_of_range_faces_are_rejected() {
+        let json = r#"{
+            "vertices": [{"x": 0, "y": 0, "z": 0}],
+            "faces": [[0, 0, 1]],
+            "material": {"Lambertian": {"albedo": {"x": 1, "y": 1, "z": 1}}}
+        }"#;
+
+        let err = serde_json::from_str::<Mesh>(json).err().unwrap();
+
+        assert!(err.to_string().contains("refers to vertex 1"));
+    }
+
+    #[test]
+    fn mismatched_normals_are_rejected() {
+        let json = r#"{
+            "vertices": [{"x": 0, "y": 0, "z": 0}],
+            "normals": [],
+            "faces": [],
+            "material": {"Lambertian": {"albedo": {"x": 1, "y": 1, "z": 1}}}
+        }"#;
+
+        let err = serde_json::from_str::<Mesh>(json).err().unwrap();
+
+        assert!(err.to_string().contains("1 vertices but 0 normals"));
+    }
+}
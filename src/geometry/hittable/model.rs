@@ -0,0 +1,339 @@
+use super::bvh::Bvh;
+use super::hitable_list::HitableList;
+use super::mesh::Mesh;
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Ray, Vec3};
+use crate::geometry::Color;
+use crate::material::Lambertian;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+mod mtl;
+mod obj;
+
+/// Triangles loaded from a Wavefront OBJ file, scaled about the model's origin and then moved
+/// by `translation`. Each material the file uses becomes its own mesh. Serializes as the
+/// description it was loaded from.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ModelData", into = "ModelData")]
+pub struct Model {
+    data: ModelData,
+    meshes: Bvh,
+    /// Where the OBJ file and any material libraries it used were read from
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelData {
+    /// Relative to the scene file's directory, or the working directory for models made in
+    /// code. Material libraries are relative to the OBJ file.
+    path: PathBuf,
+    #[serde(default)]
+    translation: Vec3,
+    #[serde(default = "unit_scale")]
+    scale: f64,
+    /// Used for every face instead of the file's own materials, whose libraries then aren't read
+    #[serde(default)]
+    material: Option<MaterialEnum>,
+}
+
+fn unit_scale() -> f64 {
+    1.
+}
+
+thread_local! {
+    /// What relative model paths are read from while a scene file is being loaded
+    static SCENE_DIRECTORY: RefCell<PathBuf> = const { RefCell::new(PathBuf::new()) };
+}
+
+impl Model {
+    pub fn load(
+        path: &Path,
+        translation: Vec3,
+        scale: f64,
+        material: Option<MaterialEnum>,
+    ) -> Result<HitEnum, ObjError> {
+        let data = ModelData {
+            path: path.to_owned(),
+            translation,
+            scale,
+            material,
+        };
+        Ok(HitEnum::Model(Self::load_data(data)?))
+    }
+
+    /// Run `load`, reading any models and environment maps it deserializes relative to
+    /// `directory`
+    pub fn in_directory<T>(directory: &Path, load: impl FnOnce() -> T) -> T {
+        let previous = SCENE_DIRECTORY.with(|current| current.replace(directory.to_owned()));
+        let result = load();
+        SCENE_DIRECTORY.with(|current| current.replace(previous));
+        result
+    }
+
+    /// Where a file named in the scene being loaded is read from
+    pub(crate) fn scene_path(path: &Path) -> PathBuf {
+        SCENE_DIRECTORY.with(|directory| directory.borrow().join(path))
+    }
+
+    fn load_data(data: ModelData) -> Result<Self, ObjError> {
+        let path = &Self::scene_path(&data.path);
+        if !data.scale.is_finite() || data.scale == 0. {
+            return Err(ObjError::Scale(path.clone(), data.scale));
+        }
+        let source = fs::read_to_string(path).map_err(|err| ObjError::Io(path.clone(), err))?;
+        let obj = obj::parse(&source).map_err(|(line, message)| ObjError::Parse {
+            path: path.clone(),
+            line,
+            message,
+        })?;
+
+        let mut files = vec![path.clone()];
+        let mut materials = HashMap::new();
+        if data.material.is_none() {
+            let directory = path.parent().unwrap_or_else(|| Path::new(""));
+            for (library, _) in &obj.libraries {
+                let library = directory.join(library);
+                let source = fs::read_to_string(&library)
+                    .map_err(|err| ObjError::Io(library.clone(), err))?;
+                let parsed = mtl::parse(&source).map_err(|(line, message)| ObjError::Parse {
+                    path: library.clone(),
+                    line,
+                    message,
+                })?;
+                materials.extend(parsed);
+                files.push(library);
+            }
+        }
+
+        // Faces are grouped by material name, in the order each material is first used
+        let mut groups: Vec<(Option<&str>, Vec<[obj::Corner; 3]>)> = Vec::new();
+        for group in &obj.groups {
+            let name = match (&data.material, &group.material) {
+                (None, Some((name, line))) if !materials.contains_key(name) => {
+                    return Err(ObjError::Parse {
+                        path: path.clone(),
+                        line: *line,
+                        message: format!("material `{}` isn't defined in any `mtllib`", name),
+                    })
+                }
+                (None, Some((name, _))) => Some(name.as_str()),
+                _ => None,
+            };
+            match groups.iter_mut().find(|(other, _)| *other == name) {
+                Some((_, faces)) => faces.extend(&group.faces),
+                None => groups.push((name, group.faces.clone())),
+            }
+        }
+
+        let default_material = data
+            .material
+            .clone()
+            .unwrap_or_else(|| Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+        let mut meshes = HitableList::new();
+        for (name, faces) in groups.into_iter().filter(|(_, faces)| !faces.is_empty()) {
+            let material = name.map_or(&default_material, |name| &materials[name]);
+            meshes.add(build_mesh(&obj, &faces, &data, material.clone()));
+        }
+
+        Ok(Self {
+            data,
+            meshes: meshes.into(),
+            files,
+        })
+    }
+}
+
+/// Give each distinct position and normal pair used by `faces` its own mesh vertex. Normals are
+/// only kept if every corner has one.
+fn build_mesh(
+    obj: &obj::Obj,
+    faces: &[[obj::Corner; 3]],
+    data: &ModelData,
+    material: MaterialEnum,
+) -> HitEnum {
+    let smooth = faces.iter().flatten().all(|corner| corner.normal.is_some());
+    let mut indices = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let faces = faces
+        .iter()
+        .map(|face| {
+            // Mirroring turns the surface inside out, so the winding is turned round to match
+            let face = if data.scale < 0. {
+                [face[0], face[2], face[1]]
+            } else {
+                *face
+            };
+            face.map(|corner| {
+                let corner = obj::Corner {
+                    normal: corner.normal.filter(|_| smooth),
+                    ..corner
+                };
+                *indices.entry(corner).or_insert_with(|| {
+                    vertices.push(obj.positions[corner.position] * data.scale + data.translation);
+                    if let Some(normal) = corner.normal {
+                        normals.push(obj.normals[normal] * data.scale.signum());
+                    }
+                    vertices.len() - 1
+                })
+            })
+        })
+        .collect();
+    Mesh::new(vertices, smooth.then_some(normals), faces, material)
+}
+
+impl TryFrom<ModelData> for Model {
+    type Error = String;
+
+    fn try_from(data: ModelData) -> Result<Self, Self::Error> {
+        Self::load_data(data).map_err(|err| err.to_string())
+    }
+}
+
+impl From<Model> for ModelData {
+    fn from(model: Model) -> Self {
+        model.data
+    }
+}
+
+impl Hittable for Model {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        self.meshes.hit(ray, t_range)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.meshes.bounding_box()
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        self.files.clone()
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    Scale(PathBuf, f64),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "Could not read model file {:?}: {}", path, err),
+            Self::Parse {
+                path,
+                line,
+                message,
+            } => write!(
+                f,
+                "Invalid model file {:?}, line {}: {}",
+                path, line, message
+            ),
+            Self::Scale(path, scale) => {
+                write!(f, "Model {:?} can't be scaled by {}", path, scale)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn load(path: &str, translation: Vec3, scale: f64) -> Result<Model, ObjError> {
+        Model::load_data(ModelData {
+            path: path.into(),
+            translation,
+            scale,
+            material: None,
+        })
+    }
+
+    #[test]
+    fn example_model_is_transformed() {
+        let model = load("scenes/models/pyramid.obj", Vec3::new(1., 2., 3.), 2.).unwrap();
+
+        let expected = Aabb::new(Vec3::new(0., 2., 2.), Vec3::new(2., 4., 4.));
+        assert_eq!(model.bounding_box(), Some(expected));
+    }
+
+    #[test]
+    fn example_model_uses_its_materials() {
+        let model = load("scenes/models/pyramid.obj", Vec3::zeros(), 1.).unwrap();
+        let ray = Ray::new(Vec3::new(0.1, -1., 0.2), Vec3::j());
+
+        let record = model.hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+        assert_eq!(record.point, Vec3::new(0.1, 0., 0.2));
+        assert!(matches!(record.material, MaterialEnum::Metal(_)));
+    }
+
+    #[test]
+    fn mirrored_models_still_face_out() {
+        let model = load("scenes/models/pyramid.obj", Vec3::zeros(), -1.).unwrap();
+        let onto_base = Ray::new(Vec3::new(0.1, 1., 0.2), -Vec3::j());
+        let onto_side = Ray::new(Vec3::new(0.1, -0.5, 3.), -Vec3::k());
+
+        for ray in [onto_base, onto_side] {
+            let record = model.hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+            assert!(record.front_face);
+            assert!(record.normal.dot(ray.direction) < 0.);
+        }
+    }
+
+    #[test]
+    fn missing_files_are_reported() {
+        let err = load("scenes/models/missing.obj", Vec3::zeros(), 1.)
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, ObjError::Io(..)));
+    }
+
+    #[test]
+    fn zero_scale_is_rejected() {
+        let err = load("scenes/models/pyramid.obj", Vec3::zeros(), 0.)
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, ObjError::Scale(..)));
+    }
+
+    #[test]
+    fn paths_are_relative_to_the_scene_directory() {
+        let json = serde_json::json!({ "path": "models/pyramid.obj" }).to_string();
+
+        let model =
+            Model::in_directory(Path::new("scenes"), || serde_json::from_str::<Model>(&json))
+                .unwrap();
+
+        assert!(model.bounding_box().is_some());
+        assert!(serde_json::from_str::<Model>(&json).is_err());
+    }
+
+    #[test]
+    fn scene_errors_include_the_line() {
+        let path =
+            std::env::temp_dir().join(format!("rust_ray_tracer_broken_{}.obj", std::process::id()));
+        fs::write(&path, "v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+        let json = serde_json::json!({ "path": path }).to_string();
+
+        let err = serde_json::from_str::<Model>(&json).err().unwrap();
+
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+}
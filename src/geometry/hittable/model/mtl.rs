@@ -0,0 +1,147 @@
+use super::obj::{parse_name, parse_number, parse_vec3};
+use crate::geometry::Color;
+use crate::material::{Dialectric, Lambertian, MaterialEnum, Metal};
+use std::collections::HashMap;
+
+/// The MTL statements that have a counterpart in our materials
+struct MtlMaterial {
+    diffuse: Color,
+    specular: Color,
+    specular_exponent: Option<f64>,
+    index_of_refraction: f64,
+    dissolve: f64,
+    illumination: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::ones(),
+            specular_exponent: None,
+            index_of_refraction: 1.5,
+            dissolve: 1.,
+            illumination: 2,
+        }
+    }
+}
+
+impl From<MtlMaterial> for MaterialEnum {
+    /// Transparent and refractive illumination models become glass, reflective ones become metal
+    /// and everything else is diffuse.
+    fn from(material: MtlMaterial) -> Self {
+        match material.illumination {
+            4 | 6 | 7 | 9 => Dialectric::new(material.index_of_refraction),
+            _ if material.dissolve < 1. => Dialectric::new(material.index_of_refraction),
+            3 | 5 | 8 => Metal::new(
+                material.specular,
+                // The usual mapping from a Phong exponent to a microfacet roughness
+                material
+                    .specular_exponent
+                    .map(|exponent| (2. / (exponent.max(0.) + 2.)).sqrt()),
+            ),
+            _ => Lambertian::new(material.diffuse),
+        }
+    }
+}
+
+/// Parse the source of an MTL file into materials by name, reporting problems as a one-based
+/// line number and message.
+pub(super) fn parse(source: &str) -> Result<HashMap<String, MaterialEnum>, (usize, String)> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            let name = parse_name(line, keyword).map_err(|message| (line_number, message))?;
+            if let Some((name, material)) = current.replace((name, MtlMaterial::default())) {
+                materials.insert(name, material.into());
+            }
+            continue;
+        }
+
+        let material = match &mut current {
+            Some((_, material)) => material,
+            None if is_material_statement(keyword) => {
+                return Err((
+                    line_number,
+                    format!("`{}` comes before any `newmtl`", keyword),
+                ))
+            }
+            None => continue,
+        };
+        let result = match keyword {
+            "Kd" => parse_vec3(&mut words).map(|color| material.diffuse = color),
+            "Ks" => parse_vec3(&mut words).map(|color| material.specular = color),
+            "Ns" => parse_single(&mut words).map(|value| material.specular_exponent = Some(value)),
+            "Ni" => parse_single(&mut words).map(|value| material.index_of_refraction = value),
+            "d" => parse_single(&mut words).map(|value| material.dissolve = value),
+            "Tr" => parse_single(&mut words).map(|value| material.dissolve = 1. - value),
+            "illum" => parse_single(&mut words)
+                .and_then(|value| {
+                    (value.fract() == 0. && value >= 0.)
+                        .then_some(value as u32)
+                        .ok_or_else(|| format!("`{}` is not an illumination model", value))
+                })
+                .map(|value| material.illumination = value),
+            _ => Ok(()),
+        };
+        result.map_err(|message| (line_number, message))?;
+    }
+    if let Some((name, material)) = current {
+        materials.insert(name, material.into());
+    }
+    Ok(materials)
+}
+
+fn is_material_statement(keyword: &str) -> bool {
+    matches!(keyword, "Kd" | "Ks" | "Ns" | "Ni" | "d" | "Tr" | "illum")
+}
+
+fn parse_single(words: &mut std::str::SplitWhitespace) -> Result<f64, String> {
+    parse_number(words.next().ok_or_else(|| "expected a number".to_owned())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn illumination_models_pick_materials() {
+        let materials = parse(
+            "newmtl matte\nKd 0.5 0.1 0.1\n\
+             newmtl shiny\nKs 0.9 0.9 0.9\nNs 200\nillum 3\n\
+             newmtl glass\nNi 1.33\nillum 7\n\
+             newmtl faded\nd 0.5\n",
+        )
+        .unwrap();
+
+        assert!(matches!(materials["matte"], MaterialEnum::Lambertian(_)));
+        assert!(matches!(materials["shiny"], MaterialEnum::Metal(_)));
+        assert!(matches!(materials["glass"], MaterialEnum::Dialectric(_)));
+        assert!(matches!(materials["faded"], MaterialEnum::Dialectric(_)));
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        let cases = [
+            ("Kd 1 1 1\n", 1, "before any `newmtl`"),
+            ("newmtl a\nKd 1 1\n", 2, "expected three numbers"),
+            ("newmtl a\n\nNs shiny\n", 3, "`shiny` is not a number"),
+            ("newmtl a\nillum 2.5\n", 2, "not an illumination model"),
+            ("newmtl\n", 1, "`newmtl` needs a name"),
+        ];
+
+        for (source, line, message) in cases {
+            let (actual_line, actual_message) = parse(source).err().unwrap();
+            assert_eq!(actual_line, line, "{}", source);
+            assert!(actual_message.contains(message), "{}", actual_message);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use super::super::{Point3, Vec3};
+use std::str::SplitWhitespace;
+
+/// The parts of an OBJ file the renderer cares about. Texture coordinates, object and group
+/// names, smoothing groups, lines and points are skipped.
+#[derive(Debug, Default)]
+pub(super) struct Obj {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    /// Material libraries named by `mtllib`, with the line they were named on
+    pub libraries: Vec<(String, usize)>,
+    pub groups: Vec<FaceGroup>,
+}
+
+/// Consecutive faces sharing the material chosen by the last `usemtl`
+#[derive(Debug, Default)]
+pub(super) struct FaceGroup {
+    /// The material's name and the line it was chosen on, or `None` before any `usemtl`
+    pub material: Option<(String, usize)>,
+    pub faces: Vec<[Corner; 3]>,
+}
+
+/// Zero-based indices into [`Obj::positions`] and [`Obj::normals`] for one corner of a face
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct Corner {
+    pub position: usize,
+    pub normal: Option<usize>,
+}
+
+/// Parse the source of an OBJ file, reporting problems as a one-based line number and message.
+/// Polygons with more than three corners are split into a fan of triangles.
+pub(super) fn parse(source: &str) -> Result<Obj, (usize, String)> {
+    let mut obj = Obj::default();
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let result = match keyword {
+            "v" => parse_vec3(&mut words).map(|position| obj.positions.push(position)),
+            "vn" => parse_vec3(&mut words).map(|normal| obj.normals.push(normal)),
+            "f" => parse_face(&mut words, &obj).map(|faces| {
+                if obj.groups.is_empty() {
+                    obj.groups.push(FaceGroup::default());
+                }
+                obj.groups.last_mut().unwrap().faces.extend(faces);
+            }),
+            "usemtl" => parse_name(line, keyword).map(|name| {
+                obj.groups.push(FaceGroup {
+                    material: Some((name, line_number)),
+                    faces: Vec::new(),
+                })
+            }),
+            "mtllib" => {
+                parse_name(line, keyword).map(|name| obj.libraries.push((name, line_number)))
+            }
+            _ => Ok(()),
+        };
+        result.map_err(|message| (line_number, message))?;
+    }
+    Ok(obj)
+}
+
+/// Everything after the keyword, which may contain spaces
+pub(super) fn parse_name(line: &str, keyword: &str) -> Result<String, String> {
+    let name = line.trim_start()[keyword.len()..].trim();
+    if name.is_empty() {
+        Err(format!("`{}` needs a name", keyword))
+    } else {
+        Ok(name.to_owned())
+    }
+}
+
+/// Three numbers, ignoring any after them like the optional `w` of a vertex
+pub(super) fn parse_vec3(words: &mut SplitWhitespace) -> Result<Vec3, String> {
+    let mut next = || -> Result<f64, String> {
+        let word = words
+            .next()
+            .ok_or_else(|| "expected three numbers".to_owned())?;
+        parse_number(word)
+    };
+    Ok(Vec3::new(next()?, next()?, next()?))
+}
+
+pub(super) fn parse_number(word: &str) -> Result<f64, String> {
+    word.parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("`{}` is not a number", word))
+}
+
+fn parse_face(words: &mut SplitWhitespace, obj: &Obj) -> Result<Vec<[Corner; 3]>, String> {
+    let corners = words
+        .map(|word| parse_corner(word, obj))
+        .collect::<Result<Vec<_>, _>>()?;
+    if corners.len() < 3 {
+        return Err(format!(
+            "face needs at least three vertices but has {}",
+            corners.len()
+        ));
+    }
+    Ok((1..corners.len() - 1)
+        .map(|i| [corners[0], corners[i], corners[i + 1]])
+        .collect())
+}
+
+/// A corner is written `v`, `v/vt`, `v//vn` or `v/vt/vn`
+fn parse_corner(word: &str, obj: &Obj) -> Result<Corner, String> {
+    let indices = word.split('/').collect::<Vec<_>>();
+    if indices.len() > 3 {
+        return Err(format!("`{}` has too many indices", word));
+    }
+    let position = resolve_index(indices[0], obj.positions.len(), "vertex")?;
+    let normal = match indices.get(2) {
+        Some(index) if !index.is_empty() => {
+            Some(resolve_index(index, obj.normals.len(), "normal")?)
+        }
+        _ => None,
+    };
+    Ok(Corner { position, normal })
+}
+
+/// Turn a one-based index, or a negative index counting back from the last element defined so
+/// far, into a zero-based index.
+fn resolve_index(word: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index = word
+        .parse::<isize>()
+        .map_err(|_| format!("`{}` is not a {} index", word, kind))?;
+    let resolved = match index {
+        0 => None,
+        index if index > 0 => Some(index as usize - 1),
+        index => count.checked_sub(index.unsigned_abs()),
+    };
+    resolved.filter(|&index| index < count).ok_or_else(|| {
+        format!(
+            "{} index {} is out of range, only {} defined so far",
+            kind, index, count
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn corner(position: usize, normal: Option<usize>) -> Corner {
+        Corner { position, normal }
+    }
+
+    #[test]
+    fn vertices_and_faces_are_read() {
+        let obj = parse(
+            "# a square\n\
+             v 0 0 0\n\
+             v 1 0 0\n\
+             v 1 1 0 1.0\n\
+             v 0 1 0\n\
+             vt 0 0\n\
+             f 1 2 3 4\n",
+        )
+        .unwrap();
+
+        assert_eq!(obj.positions[2], Vec3::new(1., 1., 0.));
+        assert_eq!(obj.groups.len(), 1);
+        assert_eq!(
+            obj.groups[0].faces,
+            vec![
+                [corner(0, None), corner(1, None), corner(2, None)],
+                [corner(0, None), corner(2, None), corner(3, None)],
+            ]
+        );
+    }
+
+    #[test]
+    fn corner_formats_and_relative_indices_are_understood() {
+        let obj = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\n\
+             f 1/1/1 2//1 -1/3\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            obj.groups[0].faces,
+            vec![[corner(0, Some(0)), corner(1, Some(0)), corner(2, None)]]
+        );
+    }
+
+    #[test]
+    fn materials_start_new_groups() {
+        let obj = parse(
+            "mtllib my materials.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             usemtl red\nf 1 2 3\n\
+             usemtl blue\nf 3 2 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(obj.libraries, vec![("my materials.mtl".to_owned(), 1)]);
+        let materials = obj
+            .groups
+            .iter()
+            .map(|group| group.material.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            materials,
+            vec![Some(("red".to_owned(), 5)), Some(("blue".to_owned(), 7))]
+        );
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        let cases = [
+            ("v 0 0 0\nv 1 zero 0\n", 2, "`zero` is not a number"),
+            ("v 0 0\n", 1, "expected three numbers"),
+            ("v 0 0 0\n\nf 1 1\n", 3, "at least three vertices"),
+            ("v 0 0 0\nf 1 1 2\n", 2, "vertex index 2 is out of range"),
+            ("v 0 0 0\nf 1 1 0\n", 2, "vertex index 0 is out of range"),
+            ("v 0 0 0\nf 1 1 -2\n", 2, "vertex index -2 is out of range"),
+            ("v 0 0 0\nf 1//1 1 1\n", 2, "normal index 1 is out of range"),
+            ("v 0 0 0\nf 1/1/1/1 1 1\n", 2, "too many indices"),
+            ("usemtl\n", 1, "`usemtl` needs a name"),
+        ];
+
+        for (source, line, message) in cases {
+            let (actual_line, actual_message) = parse(source).unwrap_err();
+            assert_eq!(actual_line, line, "{}", source);
+            assert!(actual_message.contains(message), "{}", actual_message);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Vec3};
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Vec3};
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Plane {
@@ -41,4 +41,8 @@ impl Hittable for Plane {
             None
         }
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
 }
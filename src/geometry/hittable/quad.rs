@@ -0,0 +1,252 @@
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Ray, Sampleable, Vec3};
+use crate::sampler::Sampler;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A parallelogram with one corner at `corner` and the two edges leaving it along `u` and `v`.
+/// Its outward normal points along `u × v`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "QuadData", into = "QuadData")]
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    material: MaterialEnum,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuadData {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    material: MaterialEnum,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: MaterialEnum) -> HitEnum {
+        HitEnum::Quad(Self {
+            corner,
+            u,
+            v,
+            material,
+        })
+    }
+
+    /// `t` along the ray where it crosses the quad, if it does within `t_range`
+    fn intersect(&self, ray: &Ray, t_range: Range<f64>) -> Option<f64> {
+        let normal = self.u.cross(self.v);
+        let denominator = normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = normal.dot(self.corner - ray.origin) / denominator;
+        if !t_range.contains(&t) {
+            return None;
+        }
+
+        // Where the hit falls in terms of the edges, which both run from 0 to 1 on the quad
+        let offset = ray.at(t) - self.corner;
+        let scale = normal / normal.length_squared();
+        let alpha = scale.dot(offset.cross(self.v));
+        let beta = scale.dot(self.u.cross(offset));
+        ((0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta)).then_some(t)
+    }
+}
+
+impl TryFrom<QuadData> for Quad {
+    type Error = String;
+
+    fn try_from(data: QuadData) -> Result<Self, Self::Error> {
+        // With no area there's nothing to hit, nor to sample as a light
+        if data.u.cross(data.v).near_zero() {
+            return Err("a quad's edges can't be zero or parallel".into());
+        }
+        Ok(Self {
+            corner: data.corner,
+            u: data.u,
+            v: data.v,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Quad> for QuadData {
+    fn from(quad: Quad) -> Self {
+        Self {
+            corner: quad.corner,
+            u: quad.u,
+            v: quad.v,
+            material: quad.material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let t = self.intersect(ray, t_range)?;
+        Some(HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            self.u.cross(self.v).unit_vector(),
+            self.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let far = self.corner + self.u + self.v;
+        let sides = Aabb::new(self.corner + self.u, self.corner + self.v);
+        Some(Aabb::new(self.corner, far).surrounding(sides))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        if self.material.is_emissive() {
+            vec![HitEnum::Quad(self.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Sampleable for Quad {
+    /// Directions toward points spread evenly over the quad's area
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        let [a, b] = sampler.get_2d();
+        let point = self.corner + a * self.u + b * self.v;
+        (point - origin).unit_vector()
+    }
+
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let t = match self.intersect(&ray, 0.001..f64::INFINITY) {
+            Some(t) => t,
+            None => return 0.,
+        };
+        let normal = self.u.cross(self.v);
+        let area = normal.length();
+        let cosine = normal.dot(ray.direction).abs() / area;
+        if cosine < 1e-12 {
+            return 0.;
+        }
+        t * t / (cosine * area)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::{DiffuseLight, Lambertian};
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    /// Two by one, facing `+z` at `z = -1`
+    fn quad() -> Quad {
+        Quad {
+            corner: Vec3::new(-1., 0., -1.),
+            u: 2. * Vec3::i(),
+            v: Vec3::j(),
+            material: DiffuseLight::new(Vec3::ones()),
+        }
+    }
+
+    #[test]
+    fn ray_through_face_hits_with_outward_normal() {
+        let ray = Ray::new(Vec3::new(0.9, 0.5, 0.), -Vec3::k());
+
+        let record = quad().hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+        assert_eq!(record.t, 1.);
+        assert_eq!(record.normal, Vec3::k());
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn ray_past_the_edges_misses() {
+        for origin in [Vec3::new(1.1, 0.5, 0.), Vec3::new(0., -0.1, 0.)] {
+            let ray = Ray::new(origin, -Vec3::k());
+
+            assert!(quad().hit(&ray, 0.0..f64::INFINITY).is_none());
+        }
+    }
+
+    #[test]
+    fn skewed_quads_follow_their_edges() {
+        let skewed = Quad {
+            v: Vec3::new(1., 1., 0.),
+            ..quad()
+        };
+
+        let inside = Ray::new(Vec3::new(1.8, 0.9, 0.), -Vec3::k());
+        let outside = Ray::new(Vec3::new(-0.8, 0.9, 0.), -Vec3::k());
+        assert!(skewed.hit(&inside, 0.0..f64::INFINITY).is_some());
+        assert!(skewed.hit(&outside, 0.0..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn bounding_box_holds_every_corner() {
+        let bounds = quad().bounding_box().unwrap();
+
+        assert_eq!(bounds.min, Vec3::new(-1., 0., -1.));
+        assert_eq!(bounds.max, Vec3::new(1., 1., -1.));
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let mut rng = Pcg64Mcg::seed_from_u64(0);
+        let samples = 200_000;
+        let total = (0..samples)
+            .map(|_| quad().direction_pdf(Vec3::zeros(), Vec3::random_unit_vector(&mut rng)))
+            .sum::<f64>();
+
+        let integral = total * 4. * std::f64::consts::PI / samples as f64;
+        assert!((integral - 1.).abs() < 0.02, "{}", integral);
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_quad() {
+        let mut rng = Pcg64Mcg::seed_from_u64(1);
+        for _ in 0..1000 {
+            let direction = quad().sample_direction(Vec3::zeros(), &mut rng);
+
+            assert!(quad().direction_pdf(Vec3::zeros(), direction) > 0.);
+        }
+    }
+
+    #[test]
+    fn only_emissive_quads_are_lights() {
+        let wall = Quad::new(
+            Vec3::zeros(),
+            Vec3::i(),
+            Vec3::j(),
+            Lambertian::new(Vec3::ones()),
+        );
+
+        assert_eq!(quad().lights().len(), 1);
+        assert!(wall.lights().is_empty());
+    }
+
+    #[test]
+    fn degenerate_quads_are_rejected() {
+        let quad = |u: &str, v: &str| {
+            format!(
+                r#"{{ "Quad": {{
+                    "corner": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+                    "u": {},
+                    "v": {},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                u, v
+            )
+        };
+        let zero = r#"{ "x": 0.0, "y": 0.0, "z": 0.0 }"#;
+        let across = r#"{ "x": 1.0, "y": 0.0, "z": 0.0 }"#;
+        let back = r#"{ "x": -2.0, "y": 0.0, "z": 0.0 }"#;
+        let up = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+
+        assert!(serde_json::from_str::<HitEnum>(&quad(zero, up)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&quad(across, back)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&quad(across, up)).is_ok());
+    }
+}
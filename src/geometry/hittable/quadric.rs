@@ -0,0 +1,280 @@
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Ray, Vec3};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The surface where a second degree polynomial in x, y and z is zero, cut off at the edges of
+/// `bounds`. The inside is wherever the polynomial is negative, so normals point along its
+/// gradient.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "QuadricData", into = "QuadricData")]
+pub struct Quadric {
+    coefficients: QuadricCoefficients,
+    bounds: Aabb,
+    material: MaterialEnum,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuadricData {
+    coefficients: QuadricCoefficients,
+    bounds: Aabb,
+    material: MaterialEnum,
+}
+
+/// `xx·x² + yy·y² + zz·z² + xy·xy + yz·yz + xz·xz + x·x + y·y + z·z + constant`, where any
+/// term left out is zero
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuadricCoefficients {
+    pub xx: f64,
+    pub yy: f64,
+    pub zz: f64,
+    pub xy: f64,
+    pub yz: f64,
+    pub xz: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub constant: f64,
+}
+
+impl QuadricCoefficients {
+    /// Just the second degree terms
+    fn quadratic_part(&self, p: Vec3) -> f64 {
+        self.xx * p.x * p.x
+            + self.yy * p.y * p.y
+            + self.zz * p.z * p.z
+            + self.xy * p.x * p.y
+            + self.yz * p.y * p.z
+            + self.xz * p.x * p.z
+    }
+
+    fn evaluate(&self, p: Point3) -> f64 {
+        self.quadratic_part(p) + self.x * p.x + self.y * p.y + self.z * p.z + self.constant
+    }
+
+    fn gradient(&self, p: Point3) -> Vec3 {
+        Vec3::new(
+            2. * self.xx * p.x + self.xy * p.y + self.xz * p.z + self.x,
+            2. * self.yy * p.y + self.xy * p.x + self.yz * p.z + self.y,
+            2. * self.zz * p.z + self.yz * p.y + self.xz * p.x + self.z,
+        )
+    }
+}
+
+impl Quadric {
+    pub fn new(coefficients: QuadricCoefficients, bounds: Aabb, material: MaterialEnum) -> HitEnum {
+        HitEnum::Quadric(Self {
+            coefficients,
+            bounds,
+            material,
+        })
+    }
+
+    /// An axis-aligned ellipsoid reaching `radii` out from `center` along each axis
+    pub fn ellipsoid(center: Point3, radii: Vec3, material: MaterialEnum) -> HitEnum {
+        let inverse_squared = |r: f64| 1. / (r * r);
+        let (xx, yy, zz) = (
+            inverse_squared(radii.x),
+            inverse_squared(radii.y),
+            inverse_squared(radii.z),
+        );
+        let coefficients = QuadricCoefficients {
+            xx,
+            yy,
+            zz,
+            x: -2. * center.x * xx,
+            y: -2. * center.y * yy,
+            z: -2. * center.z * zz,
+            constant: center.x * center.x * xx
+                + center.y * center.y * yy
+                + center.z * center.z * zz
+                - 1.,
+            ..Default::default()
+        };
+        let radii = Vec3::new(radii.x.abs(), radii.y.abs(), radii.z.abs());
+        Self::new(
+            coefficients,
+            Aabb::new(center - radii, center + radii),
+            material,
+        )
+    }
+
+    /// Every `t` along the ray's line where it crosses the surface inside the bounds, nearest
+    /// first
+    fn crossings(&self, ray: &Ray) -> Vec<f64> {
+        // Substituting the ray gives a·t² + 2·half_b·t + c
+        let a = self.coefficients.quadratic_part(ray.direction);
+        let half_b = 0.5 * self.coefficients.gradient(ray.origin).dot(ray.direction);
+        let c = self.coefficients.evaluate(ray.origin);
+
+        let ts = if a.abs() < 1e-12 {
+            // Parallel to an asymptote, so it only crosses once
+            if half_b.abs() < 1e-12 {
+                vec![]
+            } else {
+                vec![-c / (2. * half_b)]
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant < 0. {
+                vec![]
+            } else {
+                let root = discriminant.sqrt();
+                let (near, far) = ((-half_b - root) / a, (-half_b + root) / a);
+                vec![near.min(far), near.max(far)]
+            }
+        };
+
+        // A little slack so points right on the edge of the bounds aren't lost to rounding
+        let margin = 1e-9 * (self.bounds.max - self.bounds.min).max_component().max(1.);
+        ts.into_iter()
+            .filter(|&t| self.bounds.contains(ray.at(t), margin))
+            .collect()
+    }
+}
+
+impl TryFrom<QuadricData> for Quadric {
+    type Error = String;
+
+    fn try_from(data: QuadricData) -> Result<Self, Self::Error> {
+        if (0..3).any(|axis| data.bounds.min[axis] > data.bounds.max[axis]) {
+            return Err("a quadric's bounds can't have a minimum above their maximum".into());
+        }
+        Ok(Self {
+            coefficients: data.coefficients,
+            bounds: data.bounds,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Quadric> for QuadricData {
+    fn from(quadric: Quadric) -> Self {
+        Self {
+            coefficients: quadric.coefficients,
+            bounds: quadric.bounds,
+            material: quadric.material,
+        }
+    }
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let t = self
+            .crossings(ray)
+            .into_iter()
+            .find(|t| t_range.contains(t))?;
+        let point = ray.at(t);
+        Some(HitRecord::new(
+            point,
+            t,
+            ray,
+            self.coefficients.gradient(point).unit_vector(),
+            self.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::material::Lambertian;
+
+    fn ellipsoid() -> HitEnum {
+        Quadric::ellipsoid(
+            Vec3::new(1., 0., 0.),
+            Vec3::new(2., 1., 3.),
+            Lambertian::new(Vec3::ones()),
+        )
+    }
+
+    #[test]
+    fn ellipsoids_are_hit_at_their_radii() {
+        for (origin, t, normal) in [
+            (Vec3::new(10., 0., 0.), 7., Vec3::i()),
+            (Vec3::new(1., -10., 0.), 9., -Vec3::j()),
+            (Vec3::new(1., 0., 10.), 7., Vec3::k()),
+        ] {
+            let ray = Ray::new(origin, -origin + Vec3::i());
+
+            let record = ellipsoid().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+            assert!((record.t - t).abs() < 1e-9, "{}", record.t);
+            assert_near(record.normal, normal);
+            assert!(record.front_face);
+        }
+    }
+
+    #[test]
+    fn grazing_rays_only_hit_inside_the_surface() {
+        let inside = Ray::new(Vec3::new(1., 1. - 1e-6, 10.), -Vec3::k());
+        let outside = Ray::new(Vec3::new(1., 1. + 1e-6, 10.), -Vec3::k());
+
+        assert!(ellipsoid().hit(&inside, 0.001..f64::INFINITY).is_some());
+        assert!(ellipsoid().hit(&outside, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rays_from_inside_hit_back_faces() {
+        let ray = Ray::new(Vec3::new(1., 0., 0.), Vec3::j());
+
+        let record = ellipsoid().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 1.).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert_near(record.normal, -Vec3::j());
+    }
+
+    #[test]
+    fn open_surfaces_are_cut_off_by_their_bounds() {
+        // A hyperboloid of one sheet, x² + y² - z² = 1, between z = -1 and z = 1
+        let json = r#"{ "Quadric": {
+            "coefficients": { "xx": 1.0, "yy": 1.0, "zz": -1.0, "constant": -1.0 },
+            "bounds": {
+                "min": { "x": -2.0, "y": -2.0, "z": -1.0 },
+                "max": { "x": 2.0, "y": 2.0, "z": 1.0 }
+            },
+            "material": { "Lambertian": { "albedo": { "x": 1.0, "y": 1.0, "z": 1.0 } } }
+        } }"#;
+        let hyperboloid: HitEnum = serde_json::from_str(json).unwrap();
+
+        let waist = Ray::new(Vec3::new(5., 0., 0.), -Vec3::i());
+        let record = hyperboloid.hit(&waist, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 4.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::i());
+
+        // Past the rim the surface carries on, but it's been cut away
+        let beyond = Ray::new(Vec3::new(5., 0., 1.5), -Vec3::i());
+        assert!(hyperboloid.hit(&beyond, 0.001..f64::INFINITY).is_none());
+
+        // Down the middle the ray escapes through the open ends
+        let through = Ray::new(Vec3::new(0., 0., 5.), -Vec3::k());
+        assert!(hyperboloid.hit(&through, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn inside_out_bounds_are_rejected() {
+        let quadric = |min_z: f64| {
+            format!(
+                r#"{{ "Quadric": {{
+                    "coefficients": {{ "xx": 1.0, "yy": 1.0, "zz": 1.0, "constant": -1.0 }},
+                    "bounds": {{
+                        "min": {{ "x": -1.0, "y": -1.0, "z": {} }},
+                        "max": {{ "x": 1.0, "y": 1.0, "z": 1.0 }}
+                    }},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                min_z
+            )
+        };
+
+        assert!(serde_json::from_str::<HitEnum>(&quadric(2.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&quadric(-1.)).is_ok());
+    }
+}
@@ -1,6 +1,11 @@
+use std::f64::consts::PI;
+
 use crate::material::MaterialEnum;
+use crate::sampler::Sampler;
 
-use super::{HitEnum, HitRecord, Hittable, Point3};
+use super::{
+    Aabb, Boundary, HitEnum, HitRecord, Hittable, Interval, Point3, Sampleable, Solid, Vec3,
+};
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Sphere {
@@ -49,4 +54,161 @@ impl Hittable for Sphere {
             self.material.clone(),
         ))
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = self.radius.abs() * Vec3::ones();
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        if self.material.is_emissive() {
+            vec![HitEnum::Sphere(self.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Solid for Sphere {
+    fn intervals(&self, ray: &super::Ray) -> Vec<Interval<'_>> {
+        let origin_to_center = ray.origin - self.center;
+        let a = ray.direction.length_squared();
+        let half_b = origin_to_center.dot(ray.direction);
+        let c = origin_to_center.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0. {
+            return Vec::new();
+        }
+        // Outward even for the negative radii used to hollow out glass
+        let boundary = |t: f64| Boundary {
+            t,
+            outward_normal: (ray.at(t) - self.center) / self.radius.abs(),
+            material: &self.material,
+        };
+        let root = discriminant.sqrt();
+        vec![Interval {
+            enter: boundary((-half_b - root) / a),
+            exit: boundary((-half_b + root) / a),
+        }]
+    }
+}
+
+impl Sphere {
+    /// Cosine of the half angle of the cone the sphere fills as seen from `origin`, or `None`
+    /// from inside, where it fills every direction
+    fn cos_theta_max(&self, origin: Point3) -> Option<f64> {
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        (distance_squared > radius_squared).then(|| (1. - radius_squared / distance_squared).sqrt())
+    }
+}
+
+impl Sampleable for Sphere {
+    /// Directions are spread evenly over the cone the sphere fills, or over every direction
+    /// from inside
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        let cos_theta_max = match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => cos_theta_max,
+            None => return Vec3::random_unit_vector(sampler),
+        };
+        let [u, v] = sampler.get_2d();
+        let z = 1. + u * (cos_theta_max - 1.);
+        let phi = 2. * PI * v;
+        let sin_theta = (1. - z * z).sqrt();
+
+        let to_center = (self.center - origin).unit_vector();
+        let (a, b) = to_center.perpendiculars();
+        (sin_theta * phi.cos() * a + sin_theta * phi.sin() * b + z * to_center).unit_vector()
+    }
+
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => {
+                let to_center = (self.center - origin).unit_vector();
+                if direction.dot(to_center) >= cos_theta_max {
+                    1. / (2. * PI * (1. - cos_theta_max))
+                } else {
+                    0.
+                }
+            }
+            None => 1. / (4. * PI),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Ray;
+    use crate::material::DiffuseLight;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    fn sphere() -> Sphere {
+        match Sphere::new(Vec3::new(1., 2., -3.), 0.5, DiffuseLight::new(Vec3::ones())) {
+            HitEnum::Sphere(sphere) => sphere,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn direction_pdf_integrates_to_one() {
+        let mut rng = Pcg64Mcg::seed_from_u64(0);
+        for origin in [Vec3::new(1., 2., -2.), Vec3::new(1., 2.2, -3.)] {
+            let samples = 200_000;
+            let total = (0..samples)
+                .map(|_| sphere().direction_pdf(origin, Vec3::random_unit_vector(&mut rng)))
+                .sum::<f64>();
+
+            let integral = total * 4. * PI / samples as f64;
+            assert!((integral - 1.).abs() < 0.02, "{}", integral);
+        }
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_sphere() {
+        let mut rng = Pcg64Mcg::seed_from_u64(1);
+        let sphere = sphere();
+        for _ in 0..1000 {
+            let direction = sphere.sample_direction(Vec3::zeros(), &mut rng);
+            let ray = Ray::new(Vec3::zeros(), direction);
+
+            assert!(sphere.hit(&ray, 0.0..f64::INFINITY).is_some());
+            assert!(sphere.direction_pdf(Vec3::zeros(), direction) > 0.);
+        }
+    }
+
+    #[test]
+    fn only_emissive_spheres_are_lights() {
+        let lamp = Sphere::new(Vec3::zeros(), 1., DiffuseLight::new(Vec3::ones()));
+        let ball = Sphere::new(
+            Vec3::zeros(),
+            1.,
+            crate::material::Lambertian::new(Vec3::ones()),
+        );
+
+        assert_eq!(lamp.lights().len(), 1);
+        assert!(ball.lights().is_empty());
+    }
+
+    #[test]
+    fn grazing_rays_only_hit_inside_the_radius() {
+        let inside = Ray::new(Vec3::new(1.5 - 1e-6, 2., 5.), -Vec3::k());
+        let outside = Ray::new(Vec3::new(1.5 + 1e-6, 2., 5.), -Vec3::k());
+
+        assert!(sphere().hit(&inside, 0.001..f64::INFINITY).is_some());
+        assert!(sphere().hit(&outside, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn rays_from_inside_hit_back_faces() {
+        let ray = Ray::new(Vec3::new(1., 2., -3.), Vec3::i());
+
+        let record = sphere().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 0.5).abs() < 1e-12);
+        assert!(!record.front_face);
+        assert_eq!(record.normal, -Vec3::i());
+    }
 }
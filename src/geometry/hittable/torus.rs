@@ -0,0 +1,327 @@
+use super::{
+    Aabb, Boundary, HitEnum, HitRecord, Hittable, Interval, MaterialEnum, Point3, Ray, Solid, Vec3,
+};
+use crate::geometry::polynomial;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A ring doughnut: the surface swept by a circle of `minor_radius` whose center goes round a
+/// circle of `major_radius` about `axis` through `center`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "TorusData", into = "TorusData")]
+pub struct Torus {
+    center: Point3,
+    axis: Vec3,
+    major_radius: f64,
+    minor_radius: f64,
+    material: MaterialEnum,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TorusData {
+    center: Point3,
+    axis: Vec3,
+    major_radius: f64,
+    minor_radius: f64,
+    material: MaterialEnum,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: MaterialEnum,
+    ) -> HitEnum {
+        HitEnum::Torus(Self {
+            center,
+            axis,
+            major_radius: major_radius.abs(),
+            minor_radius: minor_radius.abs(),
+            material,
+        })
+    }
+
+    /// Every `t` along the ray's whole line where it crosses the surface, nearest first
+    fn crossings(&self, ray: &Ray) -> Vec<f64> {
+        let (origin, direction) = self.to_local(ray);
+        let (big, small) = (self.major_radius, self.minor_radius);
+
+        // Substituting the ray into (|p|² + R² - r²)² = 4R²(x² + y²) gives a quartic in t
+        let distance_squared = origin.length_squared();
+        let k = distance_squared + big * big - small * small;
+        let f = origin.dot(direction);
+        let four_big_squared = 4. * big * big;
+        let coefficients = [
+            k * k - four_big_squared * (distance_squared - origin.z * origin.z),
+            4. * f * k - 2. * four_big_squared * (f - origin.z * direction.z),
+            4. * f * f + 2. * k - four_big_squared * (1. - direction.z * direction.z),
+            4. * f,
+            1.,
+        ];
+
+        // Nothing further away than the far side of the torus can be hit, which keeps the search
+        // finite. The search leaves out its ends, so go a little further to keep hits right on
+        // the edge.
+        let reach = 1.001 * (distance_squared.sqrt() + big + small);
+        polynomial::roots(&coefficients, -reach..reach)
+    }
+
+    fn is_inside(&self, point: Point3) -> bool {
+        let axis = self.axis.unit_vector();
+        let offset = point - self.center;
+        let (big, small) = (self.major_radius, self.minor_radius);
+        let distance_squared = offset.length_squared();
+        let height = offset.dot(axis);
+        let k = distance_squared + big * big - small * small;
+        k * k < 4. * big * big * (distance_squared - height * height)
+    }
+
+    /// The ray's origin and direction in a frame with the torus at the origin and its axis
+    /// along z
+    fn to_local(&self, ray: &Ray) -> (Vec3, Vec3) {
+        let axis = self.axis.unit_vector();
+        let (u, v) = axis.perpendiculars();
+        let local = |x: Vec3| Vec3::new(x.dot(u), x.dot(v), x.dot(axis));
+        (local(ray.origin - self.center), local(ray.direction))
+    }
+
+    fn outward_normal(&self, point: Point3) -> Vec3 {
+        // Away from the nearest point on the circle running through the middle of the tube
+        let axis = self.axis.unit_vector();
+        let offset = point - self.center;
+        let in_plane = offset - offset.dot(axis) * axis;
+        let ring = if in_plane.near_zero() {
+            Vec3::zeros()
+        } else {
+            self.major_radius * in_plane.unit_vector()
+        };
+        (offset - ring).unit_vector()
+    }
+}
+
+impl TryFrom<TorusData> for Torus {
+    type Error = String;
+
+    fn try_from(data: TorusData) -> Result<Self, Self::Error> {
+        if data.axis.near_zero() {
+            return Err("a torus's axis can't be zero".into());
+        }
+        for radius in [data.major_radius, data.minor_radius] {
+            if !(radius >= 0. && radius.is_finite()) {
+                return Err("a torus's radii can't be negative or infinite".into());
+            }
+        }
+        Ok(Self {
+            center: data.center,
+            axis: data.axis,
+            major_radius: data.major_radius,
+            minor_radius: data.minor_radius,
+            material: data.material,
+        })
+    }
+}
+
+impl From<Torus> for TorusData {
+    fn from(torus: Torus) -> Self {
+        Self {
+            center: torus.center,
+            axis: torus.axis,
+            major_radius: torus.major_radius,
+            minor_radius: torus.minor_radius,
+            material: torus.material,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let t = self
+            .crossings(ray)
+            .into_iter()
+            .find(|t| t_range.contains(t))?;
+        let point = ray.at(t);
+        Some(HitRecord::new(
+            point,
+            t,
+            ray,
+            self.outward_normal(point),
+            self.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let axis = self.axis.unit_vector();
+        let reach = |component: f64| {
+            self.major_radius * (1. - component * component).max(0.).sqrt() + self.minor_radius
+        };
+        let extent = Vec3::new(reach(axis.x), reach(axis.y), reach(axis.z));
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
+
+impl Solid for Torus {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let boundary = |t: f64| Boundary {
+            t,
+            outward_normal: self.outward_normal(ray.at(t)),
+            material: &self.material,
+        };
+
+        // Grazing rays touch the surface without going in, so check what's between crossings
+        // rather than pairing them up
+        let mut intervals: Vec<Interval> = Vec::new();
+        for pair in self.crossings(ray).windows(2) {
+            if !self.is_inside(ray.at(0.5 * (pair[0] + pair[1]))) {
+                continue;
+            }
+            match intervals.last_mut() {
+                Some(last) if last.exit.t == pair[0] => last.exit = boundary(pair[1]),
+                _ => intervals.push(Interval {
+                    enter: boundary(pair[0]),
+                    exit: boundary(pair[1]),
+                }),
+            }
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::geometry::{Matrix4, Transform};
+    use crate::material::Lambertian;
+
+    /// Lying flat around the y axis, spanning three units either side
+    fn torus() -> HitEnum {
+        Torus::new(
+            Vec3::zeros(),
+            Vec3::j(),
+            2.,
+            1.,
+            Lambertian::new(Vec3::ones()),
+        )
+    }
+
+    #[test]
+    fn outer_edge_is_hit_with_outward_normal() {
+        let ray = Ray::new(Vec3::new(10., 0., 0.), -Vec3::i());
+
+        let record = torus().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 7.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::i());
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn top_of_the_tube_faces_up() {
+        let ray = Ray::new(Vec3::new(2., 5., 0.), -Vec3::j());
+
+        let record = torus().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 4.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::j());
+    }
+
+    #[test]
+    fn rays_down_the_axis_pass_through_the_hole() {
+        let ray = Ray::new(5. * Vec3::j(), -Vec3::j());
+
+        assert!(torus().hit(&ray, 0.001..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn grazing_rays_only_hit_inside_the_tube() {
+        for (offset, hits) in [(1. - 1e-6, true), (1. + 1e-6, false)] {
+            // Skimming the top of the tube
+            let ray = Ray::new(Vec3::new(10., offset, 0.), -Vec3::i());
+            assert_eq!(torus().hit(&ray, 0.001..f64::INFINITY).is_some(), hits);
+
+            // Skimming the outside of the ring
+            let ray = Ray::new(Vec3::new(2. + offset, 0., 10.), -Vec3::k());
+            assert_eq!(torus().hit(&ray, 0.001..f64::INFINITY).is_some(), hits);
+        }
+    }
+
+    #[test]
+    fn rays_from_inside_the_tube_hit_back_faces() {
+        let ray = Ray::new(Vec3::new(2., 0., 0.), Vec3::j());
+
+        let record = torus().hit(&ray, 0.001..f64::INFINITY).unwrap();
+
+        assert!((record.t - 1.).abs() < 1e-9);
+        assert!(!record.front_face);
+        assert_near(record.normal, -Vec3::j());
+    }
+
+    #[test]
+    fn rays_through_the_hole_hit_the_far_side() {
+        let ray = Ray::new(Vec3::new(-10., 0., 0.), Vec3::i());
+
+        let torus = torus();
+        let intervals = torus.intervals(&ray);
+
+        assert_eq!(intervals.len(), 2);
+        for (interval, (enter, exit)) in intervals.iter().zip([(7., 9.), (11., 13.)]) {
+            assert!((interval.enter.t - enter).abs() < 1e-9);
+            assert!((interval.exit.t - exit).abs() < 1e-9);
+        }
+        assert_near(intervals[0].enter.outward_normal, -Vec3::i());
+        assert_near(intervals[0].exit.outward_normal, Vec3::i());
+    }
+
+    #[test]
+    fn bounding_box_fits_the_ring() {
+        let bounds = torus().bounding_box().unwrap();
+
+        assert_near(bounds.min, Vec3::new(-3., -1., -3.));
+        assert_near(bounds.max, Vec3::new(3., 1., 3.));
+    }
+
+    #[test]
+    fn stood_on_edge_by_a_transform() {
+        let matrix =
+            Matrix4::translation(Vec3::new(0., 0., -5.)) * Matrix4::rotation(Vec3::k(), 90.);
+        let upright = Transform::new(torus(), matrix).unwrap();
+
+        // The hole now faces along x
+        let through_hole = Ray::new(Vec3::new(10., 0., -5.), -Vec3::i());
+        assert!(upright.hit(&through_hole, 0.001..f64::INFINITY).is_none());
+
+        let record = upright
+            .hit(
+                &Ray::new(Vec3::new(0., 10., -5.), -Vec3::j()),
+                0.001..f64::INFINITY,
+            )
+            .unwrap();
+        assert!((record.t - 7.).abs() < 1e-9);
+        assert_near(record.normal, Vec3::j());
+    }
+
+    #[test]
+    fn degenerate_tori_are_rejected() {
+        let torus = |axis: &str, minor_radius: f64| {
+            format!(
+                r#"{{ "Torus": {{
+                    "center": {{ "x": 0.0, "y": 0.0, "z": 0.0 }},
+                    "axis": {},
+                    "major_radius": 2.0,
+                    "minor_radius": {},
+                    "material": {{ "Lambertian": {{ "albedo": {{ "x": 1.0, "y": 1.0, "z": 1.0 }} }} }}
+                }} }}"#,
+                axis, minor_radius
+            )
+        };
+        let zero = r#"{ "x": 0.0, "y": 0.0, "z": 0.0 }"#;
+        let up = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+
+        assert!(serde_json::from_str::<HitEnum>(&torus(zero, 1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&torus(up, -1.)).is_err());
+        assert!(serde_json::from_str::<HitEnum>(&torus(up, 1.)).is_ok());
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::hitable_list::HitableList;
+use super::{Aabb, HitEnum, HitRecord, Hittable, Interval, Point3, Ray, Sampleable, Solid, Vec3};
+use crate::geometry::Matrix4;
+use crate::sampler::Sampler;
+
+/// Another object moved into place by an affine matrix. Rays are taken into the object's own
+/// space to be traced, and what they hit is brought back out. The object is shared, so placing
+/// one many times costs a matrix each rather than copies of the object. Scene files get the same
+/// sharing by naming the object under the scene's `objects` and giving that name as `object`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "TransformData", into = "TransformData")]
+pub struct Transform {
+    data: TransformData,
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+/// How a transform is written in scene files. The object is scaled, then rotated, then
+/// translated, each step being optional, after any `matrix` has been applied.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformData {
+    object: PlacedObject,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    matrix: Option<Matrix4>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scale: Option<Scale>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rotate: Option<Rotation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    translate: Option<Vec3>,
+}
+
+/// The object a transform places. Scene files can give it as the name of one of the scene's
+/// `objects` instead, which is only looked up once the whole scene has been read.
+#[derive(Clone)]
+enum PlacedObject {
+    Inline(Arc<HitEnum>),
+    Named {
+        name: String,
+        object: Option<Arc<HitEnum>>,
+    },
+}
+
+/// What an unresolved name stands in for, which is nothing at all
+static NOTHING: HitEnum = HitEnum::List(HitableList::new());
+
+impl Deref for PlacedObject {
+    type Target = HitEnum;
+
+    fn deref(&self) -> &HitEnum {
+        match self {
+            Self::Inline(object)
+            | Self::Named {
+                object: Some(object),
+                ..
+            } => object,
+            Self::Named { object: None, .. } => &NOTHING,
+        }
+    }
+}
+
+impl Serialize for PlacedObject {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Inline(object) => object.serialize(serializer),
+            Self::Named { name, .. } => serializer.serialize_str(name),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PlacedObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PlacedVisitor;
+
+        impl<'de> Visitor<'de> for PlacedVisitor {
+            type Value = PlacedObject;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object or the name of one from the scene's objects")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(PlacedObject::Named {
+                    name: name.to_owned(),
+                    object: None,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let object = HitEnum::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(PlacedObject::Inline(Arc::new(object)))
+            }
+        }
+
+        deserializer.deserialize_any(PlacedVisitor)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Scale {
+    Uniform(f64),
+    PerAxis(Vec3),
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rotation {
+    axis: Vec3,
+    degrees: f64,
+}
+
+impl Transform {
+    /// Place `object` with `matrix`, or `None` if the matrix isn't affine or can't be undone.
+    /// Lists are put into a `Bvh` first so the rays taken into them stay quick to trace.
+    pub fn new(object: impl Into<Arc<HitEnum>>, matrix: Matrix4) -> Option<HitEnum> {
+        let data = TransformData {
+            object: PlacedObject::Inline(object.into()),
+            matrix: Some(matrix),
+            scale: None,
+            rotate: None,
+            translate: None,
+        };
+        Self::try_from(data)
+            .ok()
+            .map(|transform| HitEnum::Transform(Box::new(transform)))
+    }
+}
+
+impl TryFrom<TransformData> for Transform {
+    type Error = String;
+
+    fn try_from(mut data: TransformData) -> Result<Self, Self::Error> {
+        let mut matrix = data.matrix.unwrap_or_default();
+        if !matrix.is_affine() {
+            return Err("a transform's matrix must end with the row [0, 0, 0, 1]".into());
+        }
+        if let Some(scale) = data.scale {
+            let factors = match scale {
+                Scale::Uniform(factor) => factor * Vec3::ones(),
+                Scale::PerAxis(factors) => factors,
+            };
+            matrix = Matrix4::scaling(factors) * matrix;
+        }
+        if let Some(Rotation { axis, degrees }) = data.rotate {
+            if axis.near_zero() {
+                return Err("a transform can't rotate about a zero axis".into());
+            }
+            matrix = Matrix4::rotation(axis, degrees) * matrix;
+        }
+        if let Some(offset) = data.translate {
+            matrix = Matrix4::translation(offset) * matrix;
+        }
+        let inverse = matrix
+            .inverse()
+            .ok_or("a transform can't squash its object flat")?;
+
+        if let PlacedObject::Inline(object) = &data.object {
+            if let HitEnum::List(list) = &**object {
+                data.object = PlacedObject::Inline(Arc::new(HitEnum::Bvh(list.clone().into())));
+            }
+        }
+        Ok(Self {
+            data,
+            matrix,
+            inverse,
+        })
+    }
+}
+
+impl From<Transform> for TransformData {
+    fn from(transform: Transform) -> Self {
+        transform.data
+    }
+}
+
+impl Transform {
+    /// Whether the placed object is solid, taking names that haven't been looked up yet to be,
+    /// since whatever holds the transform is checked again once they have
+    pub(super) fn is_solid(&self) -> bool {
+        match &self.data.object {
+            PlacedObject::Named { object: None, .. } => true,
+            object => object.is_solid(),
+        }
+    }
+
+    pub(super) fn unresolved_names(&self) -> Vec<&str> {
+        match &self.data.object {
+            PlacedObject::Inline(object) => object.unresolved_names(),
+            PlacedObject::Named { name, object: None } => vec![name],
+            PlacedObject::Named { .. } => Vec::new(),
+        }
+    }
+
+    /// Look up the names placed by this transform, or inside what it places, among `objects`
+    pub(super) fn resolve(self, objects: &BTreeMap<String, Arc<HitEnum>>) -> Result<Self, String> {
+        let object = match self.data.object {
+            PlacedObject::Inline(object) => {
+                PlacedObject::Inline(Arc::new(Arc::unwrap_or_clone(object).resolve(objects)?))
+            }
+            PlacedObject::Named { name, object: None } => {
+                let object = objects.get(&name).cloned().ok_or_else(|| {
+                    format!(
+                        "no object named {:?} is defined in the scene's objects",
+                        name
+                    )
+                })?;
+                PlacedObject::Named {
+                    name,
+                    object: Some(object),
+                }
+            }
+            object => object,
+        };
+        Ok(Self {
+            data: TransformData {
+                object,
+                ..self.data
+            },
+            ..self
+        })
+    }
+
+    /// The ray in object space, and how much longer its direction got on the way there
+    fn object_ray(&self, ray: &Ray) -> (Ray, f64) {
+        let direction = self.inverse.transform_vector(ray.direction);
+        let stretch = direction.length();
+        let origin = self.inverse.transform_point(ray.origin);
+        (Ray::new(origin, direction), stretch)
+    }
+
+    /// Normals go through the inverse transpose, to stay perpendicular to the surface
+    fn world_normal(&self, normal: Vec3) -> Vec3 {
+        self.inverse
+            .transpose()
+            .transform_vector(normal)
+            .unit_vector()
+    }
+
+    /// The same transform around another object
+    fn around(&self, object: HitEnum) -> HitEnum {
+        HitEnum::Transform(Box::new(Self {
+            data: TransformData {
+                object: PlacedObject::Inline(Arc::new(object)),
+                ..self.data.clone()
+            },
+            ..*self
+        }))
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        // Rays are kept at unit length, so distances along them in object space are
+        // `stretch` times those in world space
+        let (object_ray, stretch) = self.object_ray(ray);
+        let record = self
+            .data
+            .object
+            .hit(&object_ray, t_range.start * stretch..t_range.end * stretch)?;
+        Some(HitRecord {
+            point: self.matrix.transform_point(record.point),
+            normal: self.world_normal(record.normal),
+            t: record.t / stretch,
+            ..record
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = self.data.object.bounding_box()?;
+        let corners = (0..8).map(|corner| {
+            let pick = |axis: usize| {
+                if corner & (1 << axis) == 0 {
+                    bounds.min[axis]
+                } else {
+                    bounds.max[axis]
+                }
+            };
+            self.matrix
+                .transform_point(Vec3::new(pick(0), pick(1), pick(2)))
+        });
+        corners
+            .map(|corner| Aabb::new(corner, corner))
+            .reduce(Aabb::surrounding)
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        self.data
+            .object
+            .lights()
+            .into_iter()
+            .map(|light| self.around(light))
+            .collect()
+    }
+
+    fn source_files(&self) -> Vec<PathBuf> {
+        self.data.object.source_files()
+    }
+}
+
+impl Sampleable for Transform {
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        let direction = self
+            .data
+            .object
+            .sample_direction(self.inverse.transform_point(origin), sampler);
+        self.matrix.transform_vector(direction).unit_vector()
+    }
+
+    /// Squashing and stretching spreads directions out unevenly, by the Jacobian
+    /// `|det A| / |A ω|³` of normalizing the directions mapped by the inverse `A`
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let object_direction = self.inverse.transform_vector(direction);
+        let stretch = object_direction.length();
+        let pdf = self.data.object.direction_pdf(
+            self.inverse.transform_point(origin),
+            object_direction / stretch,
+        );
+        pdf * self.inverse.linear_determinant().abs() / stretch.powi(3)
+    }
+}
+
+impl Solid for Transform {
+    fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+        let (object_ray, stretch) = self.object_ray(ray);
+        let mut intervals = self.data.object.intervals(&object_ray);
+        for interval in &mut intervals {
+            for boundary in [&mut interval.enter, &mut interval.exit] {
+                boundary.t /= stretch;
+                boundary.outward_normal = self.world_normal(boundary.outward_normal);
+            }
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_near;
+    use crate::geometry::{HitableList, Sphere};
+    use crate::material::{DiffuseLight, Lambertian};
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    fn unit_sphere() -> HitEnum {
+        Sphere::new(Vec3::zeros(), 1., Lambertian::new(Vec3::ones()))
+    }
+
+    #[test]
+    fn scaled_and_moved_sphere_is_hit_in_world_space() {
+        let matrix =
+            Matrix4::translation(Vec3::new(0., 0., -5.)) * Matrix4::scaling(Vec3::new(2., 1., 1.));
+        let ellipsoid = Transform::new(unit_sphere(), matrix).unwrap();
+
+        let along_z = Ray::new(Vec3::zeros(), -Vec3::k());
+        let record = ellipsoid.hit(&along_z, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 4.).abs() < 1e-9);
+        assert_near(record.point, Vec3::new(0., 0., -4.));
+        assert_near(record.normal, Vec3::k());
+
+        // The long side's normal leans toward the squashed axis
+        let along_x = Ray::new(Vec3::new(-10., 0., -5.), Vec3::i());
+        let record = ellipsoid.hit(&along_x, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 8.).abs() < 1e-9);
+        assert_near(record.normal, -Vec3::i());
+        let slanted = Ray::new(Vec3::new(1., 0., 0.), -Vec3::k());
+        let normal = ellipsoid
+            .hit(&slanted, 0.001..f64::INFINITY)
+            .unwrap()
+            .normal;
+        let surface = Vec3::new(1., 0., -5. + 0.75f64.sqrt());
+        let expected = Vec3::new(surface.x / 4., 0., surface.z + 5.).unit_vector();
+        assert_near(normal, expected);
+    }
+
+    #[test]
+    fn t_range_is_respected_in_world_units() {
+        let matrix =
+            Matrix4::translation(Vec3::new(0., 0., -5.)) * Matrix4::scaling(Vec3::new(3., 3., 3.));
+        let sphere = Transform::new(unit_sphere(), matrix).unwrap();
+        let ray = Ray::new(Vec3::zeros(), -Vec3::k());
+
+        assert!(sphere.hit(&ray, 0.001..1.9).is_none());
+        assert!(sphere.hit(&ray, 0.001..2.1).is_some());
+    }
+
+    #[test]
+    fn bounding_box_covers_the_rotated_object() {
+        let matrix = Matrix4::rotation(Vec3::k(), 45.) * Matrix4::scaling(Vec3::new(2., 1., 1.));
+        let bounds = Transform::new(unit_sphere(), matrix)
+            .unwrap()
+            .bounding_box()
+            .unwrap();
+
+        let reach = 3. / 2f64.sqrt();
+        assert_near(bounds.max, Vec3::new(reach, reach, 1.));
+        assert_near(bounds.min, -Vec3::new(reach, reach, 1.));
+    }
+
+    #[test]
+    fn one_object_can_be_placed_many_times() {
+        let shared = Arc::new(unit_sphere());
+        let mut list = HitableList::new();
+        for x in [-3., 0., 3.] {
+            list.add(
+                Transform::new(shared.clone(), Matrix4::translation(Vec3::new(x, 0., 0.))).unwrap(),
+            );
+        }
+
+        assert_eq!(Arc::strong_count(&shared), 4);
+        let ray = Ray::new(Vec3::new(3., 0., 5.), -Vec3::k());
+        assert!((list.hit(&ray, 0.001..f64::INFINITY).unwrap().t - 4.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn singular_matrices_are_rejected() {
+        let flat = Matrix4::scaling(Vec3::new(1., 0., 1.));
+
+        assert!(Transform::new(unit_sphere(), flat).is_none());
+    }
+
+    #[test]
+    fn shorthand_is_read_from_scene_files() {
+        let json = r#"{ "Transform": {
+            "object": { "Sphere": {
+                "center": { "x": 0.0, "y": 0.0, "z": 0.0 },
+                "radius": 1.0,
+                "material": { "Lambertian": { "albedo": { "x": 1.0, "y": 1.0, "z": 1.0 } } }
+            } },
+            "scale": 2.0,
+            "rotate": { "axis": { "x": 0.0, "y": 1.0, "z": 0.0 }, "degrees": 90.0 },
+            "translate": { "x": 0.0, "y": 0.0, "z": -5.0 }
+        } }"#;
+        let object: HitEnum = serde_json::from_str(json).unwrap();
+
+        let ray = Ray::new(Vec3::zeros(), -Vec3::k());
+        assert!((object.hit(&ray, 0.001..f64::INFINITY).unwrap().t - 3.).abs() < 1e-9);
+        let reloaded: HitEnum =
+            serde_json::from_str(&serde_json::to_string(&object).unwrap()).unwrap();
+        assert!((reloaded.hit(&ray, 0.001..f64::INFINITY).unwrap().t - 3.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transformed_lights_pdf_integrates_to_one() {
+        let lamp = Sphere::new(Vec3::zeros(), 1., DiffuseLight::new(Vec3::ones()));
+        let matrix = Matrix4::translation(Vec3::new(0., 0., -4.))
+            * Matrix4::rotation(Vec3::j(), 30.)
+            * Matrix4::scaling(Vec3::new(2., 0.5, 1.));
+        let lights = Transform::new(lamp, matrix).unwrap().lights();
+        assert_eq!(lights.len(), 1);
+        let light = &lights[0];
+
+        let mut rng = Pcg64Mcg::seed_from_u64(3);
+        let samples = 200_000;
+        let total = (0..samples)
+            .map(|_| light.direction_pdf(Vec3::zeros(), Vec3::random_unit_vector(&mut rng)))
+            .sum::<f64>();
+        let integral = total * 4. * std::f64::consts::PI / samples as f64;
+        assert!((integral - 1.).abs() < 0.03, "{}", integral);
+
+        for _ in 0..100 {
+            let direction = light.sample_direction(Vec3::zeros(), &mut rng);
+            assert!(light
+                .hit(&Ray::new(Vec3::zeros(), direction), 0.001..f64::INFINITY)
+                .is_some());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use super::{Aabb, HitEnum, HitRecord, Hittable, MaterialEnum, Point3, Ray, Sampleable, Vec3};
+use crate::sampler::Sampler;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Triangle {
+    vertices: [Point3; 3],
+    /// Per-vertex normals to interpolate across the face for smooth shading
+    #[serde(default)]
+    normals: Option<[Vec3; 3]>,
+    material: MaterialEnum,
+}
+
+impl Triangle {
+    pub fn new(
+        vertices: [Point3; 3],
+        normals: Option<[Vec3; 3]>,
+        material: MaterialEnum,
+    ) -> HitEnum {
+        HitEnum::Triangle(Self {
+            vertices,
+            normals: normals.map(|normals| normals.map(Vec3::unit_vector)),
+            material,
+        })
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_range: Range<f64>) -> Option<HitRecord> {
+        let (t, u, v) = intersect(ray, &self.vertices, t_range)?;
+        Some(hit_record(
+            ray,
+            t,
+            u,
+            v,
+            &self.vertices,
+            self.normals.as_ref(),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(bounds(&self.vertices))
+    }
+
+    fn lights(&self) -> Vec<HitEnum> {
+        if self.material.is_emissive() {
+            vec![HitEnum::Triangle(self.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Sampleable for Triangle {
+    /// Directions toward points spread evenly over the triangle's area
+    fn sample_direction<S: Sampler + ?Sized>(&self, origin: Point3, sampler: &mut S) -> Vec3 {
+        let [v0, v1, v2] = self.vertices;
+        let [u, t] = sampler.get_2d();
+        let s = u.sqrt();
+        let point = (1. - s) * v0 + s * (1. - t) * v1 + s * t * v2;
+        (point - origin).unit_vector()
+    }
+
+    fn direction_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let t = match intersect(&ray, &self.vertices, 0.001..f64::INFINITY) {
+            Some((t, _, _)) => t,
+            None => return 0.,
+        };
+        let [v0, v1, v2] = self.vertices;
+        let doubled_area = (v1 - v0).cross(v2 - v0);
+        let cosine = doubled_area.unit_vector().dot(ray.direction).abs();
+        if cosine < 1e-12 {
+            return 0.;
+        }
+        t * t / (cosine * 0.5 * doubled_area.length())
+    }
+}
+
+/// Möller–Trumbore intersection, giving `t` and the barycentric coordinates of the hit for the
+/// second and third vertices.
+pub(super) fn intersect(
+    ray: &Ray,
+    [v0, v1, v2]: &[Point3; 3],
+    t_range: Range<f64>,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = *v1 - *v0;
+    let edge2 = *v2 - *v0;
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse_determinant = 1. / determinant;
+
+    let origin_to_v0 = ray.origin - *v0;
+    let u = origin_to_v0.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = origin_to_v0.cross(edge1);
+    let v = ray.direction.dot(q) * inverse_determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse_determinant;
+    t_range.contains(&t).then_some((t, u, v))
+}
+
+pub(super) fn hit_record(
+    ray: &Ray,
+    t: f64,
+    u: f64,
+    v: f64,
+    [v0, v1, v2]: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    material: &MaterialEnum,
+) -> HitRecord {
+    let geometric_normal = (*v1 - *v0).cross(*v2 - *v0).unit_vector();
+    let outward_normal = match normals {
+        Some([n0, n1, n2]) => {
+            let shading_normal = ((1. - u - v) * *n0 + u * *n1 + v * *n2).unit_vector();
+            // Keep the shading normal on the same side as the surface so front_face is right
+            if shading_normal.dot(geometric_normal) < 0. {
+                -shading_normal
+            } else {
+                shading_normal
+            }
+        }
+        None => geometric_normal,
+    };
+    HitRecord::new(ray.at(t), t, ray, outward_normal, material.clone())
+}
+
+pub(super) fn bounds([v0, v1, v2]: &[Point3; 3]) -> Aabb {
+    Aabb::new(v0.min(*v1).min(*v2), v0.max(*v1).max(*v2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::material::Lambertian;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    fn triangle(normals: Option<[Vec3; 3]>) -> Triangle {
+        match Triangle::new(
+            [
+                Vec3::new(0., 0., -1.),
+                Vec3::new(1., 0., -1.),
+                Vec3::new(0., 1., -1.),
+            ],
+            normals,
+            Lambertian::new(Vec3::ones()),
+        ) {
+            HitEnum::Triangle(triangle) => triangle,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn ray_through_face_hits() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 0.), -Vec3::k());
+
+        let record = triangle(None).hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+        assert_eq!(record.t, 1.);
+        assert_eq!(record.point, Vec3::new(0.25, 0.25, -1.));
+        assert_eq!(record.normal, Vec3::k());
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn ray_from_behind_hits_back_face() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -2.), Vec3::k());
+
+        let record = triangle(None).hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+        assert_eq!(record.normal, -Vec3::k());
+        assert!(!record.front_face);
+    }
+
+    #[test]
+    fn ray_outside_edges_misses() {
+        let ray = Ray::new(Vec3::new(0.75, 0.75, 0.), -Vec3::k());
+
+        assert!(triangle(None).hit(&ray, 0.0..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn parallel_ray_misses() {
+        let ray = Ray::new(Vec3::new(-1., 0.25, -1.), Vec3::i());
+
+        assert!(triangle(None).hit(&ray, 0.0..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn hit_outside_t_range_misses() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 0.), -Vec3::k());
+
+        assert!(triangle(None).hit(&ray, 0.0..0.5).is_none());
+    }
+
+    #[test]
+    fn vertex_normals_are_interpolated() {
+        let tilted = Vec3::new(1., 0., 1.);
+        let triangle = triangle(Some([Vec3::k(), tilted, Vec3::k()]));
+        let ray = Ray::new(Vec3::new(0.5, 0., 0.), -Vec3::k());
+
+        let record = triangle.hit(&ray, 0.0..f64::INFINITY).unwrap();
+
+        let expected = (0.5 * Vec3::k() + 0.5 * tilted.unit_vector()).unit_vector();
+        assert!((record.normal - expected).length() < 1e-12);
+    }
+
+    #[test]
+    fn direction_pdf_integrates_to_one() {
+        let mut rng = Pcg64Mcg::seed_from_u64(0);
+        let triangle = triangle(None);
+        let origin = Vec3::new(0.2, 0.3, -0.5);
+        let samples = 200_000;
+
+        let total = (0..samples)
+            .map(|_| triangle.direction_pdf(origin, Vec3::random_unit_vector(&mut rng)))
+            .sum::<f64>();
+
+        let integral = total * 4. * std::f64::consts::PI / samples as f64;
+        assert!((integral - 1.).abs() < 0.02, "{}", integral);
+    }
+
+    #[test]
+    fn sampled_directions_hit_the_triangle() {
+        let mut rng = Pcg64Mcg::seed_from_u64(1);
+        let triangle = triangle(None);
+        for _ in 0..1000 {
+            let direction = triangle.sample_direction(Vec3::zeros(), &mut rng);
+
+            assert!(triangle.direction_pdf(Vec3::zeros(), direction) > 0.);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::ops::Mul;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Point3, Vec3};
+
+/// A 4×4 matrix of rows, acting on points and vectors as columns with an implicit fourth
+/// coordinate of one or zero
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        let mut rows = [[0.; 4]; 4];
+        (0..4).for_each(|i| rows[i][i] = 1.);
+        Self(rows)
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut matrix = Self::identity();
+        (0..3).for_each(|i| matrix.0[i][3] = offset[i]);
+        matrix
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut matrix = Self::identity();
+        (0..3).for_each(|i| matrix.0[i][i] = factors[i]);
+        matrix
+    }
+
+    /// Turn counterclockwise about `axis`, looking down it toward the origin
+    pub fn rotation(axis: Vec3, degrees: f64) -> Self {
+        let Vec3 { x, y, z } = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let rest = 1. - cos;
+        Self([
+            [
+                cos + x * x * rest,
+                x * y * rest - z * sin,
+                x * z * rest + y * sin,
+                0.,
+            ],
+            [
+                y * x * rest + z * sin,
+                cos + y * y * rest,
+                y * z * rest - x * sin,
+                0.,
+            ],
+            [
+                z * x * rest - y * sin,
+                z * y * rest + x * sin,
+                cos + z * z * rest,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Whether the bottom row leaves the fourth coordinate alone, as translations, rotations
+    /// and scalings all do
+    pub fn is_affine(&self) -> bool {
+        self.0[3] == [0., 0., 0., 1.]
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Self(rows)
+    }
+
+    /// Gauss–Jordan elimination with partial pivoting, or `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut left = self.0;
+        let mut right = Self::identity().0;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&a, &b| left[a][column].abs().total_cmp(&left[b][column].abs()))?;
+            if left[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            left.swap(column, pivot);
+            right.swap(column, pivot);
+
+            let scale = 1. / left[column][column];
+            left[column].iter_mut().for_each(|value| *value *= scale);
+            right[column].iter_mut().for_each(|value| *value *= scale);
+            for row in (0..4).filter(|&row| row != column) {
+                let factor = left[row][column];
+                for i in 0..4 {
+                    left[row][i] -= factor * left[column][i];
+                    right[row][i] -= factor * right[column][i];
+                }
+            }
+        }
+        Some(Self(right))
+    }
+
+    /// Determinant of the upper left 3×3 block, how much the matrix scales volumes by
+    pub fn linear_determinant(&self) -> f64 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        self.transform_vector(point) + Vec3::new(self.0[0][3], self.0[1][3], self.0[2][3])
+    }
+
+    /// Transform a direction, which translations leave alone
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let row = |i: usize| Vec3::new(self.0[i][0], self.0[i][1], self.0[i][2]).dot(vector);
+        Vec3::new(row(0), row(1), row(2))
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    /// Applies `rhs` first, then `self`
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+        Self(rows)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_within;
+
+    #[test]
+    fn translations_move_points_but_not_vectors() {
+        let matrix = Matrix4::translation(Vec3::new(1., 2., 3.));
+
+        assert_eq!(matrix.transform_point(Vec3::ones()), Vec3::new(2., 3., 4.));
+        assert_eq!(matrix.transform_vector(Vec3::ones()), Vec3::ones());
+    }
+
+    #[test]
+    fn rotations_turn_counterclockwise() {
+        let matrix = Matrix4::rotation(Vec3::k(), 90.);
+
+        assert_within(matrix.transform_vector(Vec3::i()), Vec3::j(), 1e-12);
+        assert_within(matrix.transform_vector(Vec3::j()), -Vec3::i(), 1e-12);
+    }
+
+    #[test]
+    fn products_apply_the_right_matrix_first() {
+        let matrix = Matrix4::translation(Vec3::i()) * Matrix4::scaling(Vec3::new(2., 2., 2.));
+
+        assert_within(
+            matrix.transform_point(Vec3::i()),
+            Vec3::new(3., 0., 0.),
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let matrix = Matrix4::translation(Vec3::new(1., -2., 3.))
+            * Matrix4::rotation(Vec3::new(1., 1., 0.), 30.)
+            * Matrix4::scaling(Vec3::new(2., 0.5, -1.));
+        let inverse = matrix.inverse().unwrap();
+        let point = Vec3::new(0.3, 0.7, -1.1);
+
+        assert_within(
+            inverse.transform_point(matrix.transform_point(point)),
+            point,
+            1e-12,
+        );
+        assert!((matrix.linear_determinant() + 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert!(Matrix4::scaling(Vec3::new(1., 0., 1.)).inverse().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use std::ops::Range;
+
+/// Real roots within `range` of the polynomial with the given coefficients, constant term
+/// first, in increasing order.
+///
+/// Roots are isolated between the turning points, found the same way from the derivative, and
+/// then bisected, so it's slow but won't skip roots that lie close together the way closed form
+/// quartic solutions can. Turning points that touch zero count as roots, so rays grazing a
+/// surface still hit it.
+pub fn roots(coefficients: &[f64], range: Range<f64>) -> Vec<f64> {
+    let degree = match coefficients.iter().rposition(|&c| c != 0.) {
+        Some(degree) => degree,
+        None => return Vec::new(),
+    };
+    let coefficients = &coefficients[..=degree];
+    if degree == 0 {
+        return Vec::new();
+    }
+
+    let derivative = coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(power, c)| power as f64 * c)
+        .collect::<Vec<_>>();
+    let turning_points = roots(&derivative, range.clone());
+
+    // How close to zero counts as touching it, relative to the size of the terms
+    let scale = coefficients.iter().fold(0f64, |max, c| max.max(c.abs()));
+    let tolerance = 1e-12 * scale;
+
+    let mut bounds = vec![range.start];
+    bounds.extend(&turning_points);
+    bounds.push(range.end);
+    let mut found = Vec::new();
+    for pair in bounds.windows(2) {
+        let (low, high) = (pair[0], pair[1]);
+        let (f_low, f_high) = (evaluate(coefficients, low), evaluate(coefficients, high));
+        if f_low.abs() <= tolerance && low > range.start {
+            found.push(low);
+        } else if f_low.signum() != f_high.signum() && f_high.abs() > tolerance {
+            found.push(bisect(coefficients, low, high, f_low));
+        }
+    }
+    found.dedup_by(|a, b| (*a - *b).abs() <= 1e-12 * a.abs().max(1.));
+    found
+}
+
+/// Horner's method
+pub fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0., |sum, c| sum * x + c)
+}
+
+fn bisect(coefficients: &[f64], mut low: f64, mut high: f64, f_low: f64) -> f64 {
+    // Until the two ends meet in floating point
+    for _ in 0..200 {
+        let middle = 0.5 * (low + high);
+        if middle <= low || middle >= high {
+            break;
+        }
+        if evaluate(coefficients, middle).signum() == f_low.signum() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    0.5 * (low + high)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_roots(found: Vec<f64>, expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() < 1e-9, "{} != {}", found, expected);
+        }
+    }
+
+    #[test]
+    fn quartic_roots_come_out_in_order() {
+        // (x + 2)(x - 1)(x - 3)(x - 3.001)
+        let coefficients = [-18.006, 21.005, 1.002, -5.001, 1.];
+
+        assert_roots(roots(&coefficients, -10.0..10.), &[-2., 1., 3., 3.001]);
+    }
+
+    #[test]
+    fn roots_outside_the_range_are_left_out() {
+        let coefficients = [-18.006, 21.005, 1.002, -5.001, 1.];
+
+        assert_roots(roots(&coefficients, 0.0..2.), &[1.]);
+    }
+
+    #[test]
+    fn double_roots_are_found() {
+        // (x - 1)²(x + 1)
+        assert_roots(roots(&[1., -1., -1., 1.], -5.0..5.), &[-1., 1.]);
+    }
+
+    #[test]
+    fn polynomials_without_real_roots_have_none() {
+        assert!(roots(&[1., 0., 1.], -10.0..10.).is_empty());
+        assert!(roots(&[3.], -10.0..10.).is_empty());
+    }
+}
@@ -36,4 +36,11 @@ mod test {
 
         assert_eq!(expected, ray.at(3.));
     }
+
+    #[test]
+    fn ray_at_measures_distance_along_the_ray() {
+        let ray = Ray::new(Vec3::new(1., 2., 3.), Vec3::new(0., 0., 2.));
+
+        assert_eq!(Vec3::new(1., 2., 6.), ray.at(3.));
+    }
 }
@@ -1,6 +1,7 @@
-use ::std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
-use rand::rngs::ThreadRng;
-use rand_distr::{Distribution, UnitBall, UnitSphere};
+use ::std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::f64::consts::PI;
+
+use crate::sampler::Sampler;
 
 #[derive(Debug, Default, PartialEq, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Vec3 {
@@ -58,35 +59,55 @@ impl Vec3 {
         }
     }
 
+    /// The component-wise minimum of two vectors
+    pub fn min(self, other: Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    /// The component-wise maximum of two vectors
+    pub fn max(self, other: Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+
+    pub fn max_component(self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+
     pub fn unit_vector(self) -> Self {
         self / self.length()
     }
 
-    pub fn random_in_unit_sphere(rng: &mut ThreadRng) -> Self {
-        let v = UnitBall.sample(rng);
-        Self {
-            x: v[0],
-            y: v[1],
-            z: v[2],
-        }
+    pub fn random_in_unit_sphere<S: Sampler + ?Sized>(sampler: &mut S) -> Self {
+        Self::random_unit_vector(sampler) * sampler.get_1d().cbrt()
     }
 
-    pub fn random_unit_vector(rng: &mut ThreadRng) -> Self {
-        let v = UnitSphere.sample(rng);
-        Self {
-            x: v[0],
-            y: v[1],
-            z: v[2],
-        }
+    /// A random point in the unit disk on the xy plane
+    pub fn random_in_unit_disk<S: Sampler + ?Sized>(sampler: &mut S) -> Self {
+        let [u, v] = sampler.get_2d();
+        let (radius, theta) = (u.sqrt(), 2. * PI * v);
+        Self::new(radius * theta.cos(), radius * theta.sin(), 0.)
     }
 
-    pub fn random_unit_vector_in_direction(self, rng: &mut ThreadRng) -> Self {
-        let v = UnitSphere.sample(rng);
-        let v = Self {
-            x: v[0],
-            y: v[1],
-            z: v[2],
-        };
+    /// A random direction, mapping the sampler's two dimensions onto the sphere without
+    /// rejecting any so that evenly spread samples stay evenly spread
+    pub fn random_unit_vector<S: Sampler + ?Sized>(sampler: &mut S) -> Self {
+        let [u, v] = sampler.get_2d();
+        let z = 1. - 2. * u;
+        let radius = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * v;
+        Self::new(radius * phi.cos(), radius * phi.sin(), z)
+    }
+
+    pub fn random_unit_vector_in_direction<S: Sampler + ?Sized>(self, sampler: &mut S) -> Self {
+        let v = Self::random_unit_vector(sampler);
         if v.dot(self) > 0. {
             v
         } else {
@@ -94,9 +115,21 @@ impl Vec3 {
         }
     }
 
+    /// Two unit vectors perpendicular to this one and to each other
+    pub fn perpendiculars(self) -> (Self, Self) {
+        let w = self.unit_vector();
+        let helper = if w.x.abs() > 0.9 {
+            Self::j()
+        } else {
+            Self::i()
+        };
+        let v = w.cross(helper).unit_vector();
+        (w.cross(v), v)
+    }
+
     pub fn near_zero(&self) -> bool {
         let cutoff = 1e-8;
-        self.x < cutoff && self.y < cutoff && self.z < cutoff
+        self.x.abs() < cutoff && self.y.abs() < cutoff && self.z.abs() < cutoff
     }
 
     pub fn reflect(self, normal: Self) -> Self {
@@ -107,7 +140,7 @@ impl Vec3 {
         let cos_theta = (-uv).dot(n).min(1.0);
         let r_out_perp = etai_over_etat * (uv + cos_theta * n);
         let r_out_parallel = -(1.0 - r_out_perp.length_squared()).abs().sqrt() * n;
-        return r_out_perp + r_out_parallel;
+        r_out_perp + r_out_parallel
     }
 }
 
@@ -222,6 +255,19 @@ impl Neg for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 has no axis {}", axis),
+        }
+    }
+}
+
 impl From<(f64, f64, f64)> for Vec3 {
     fn from(tuple: (f64, f64, f64)) -> Self {
         Self {
@@ -232,6 +278,18 @@ impl From<(f64, f64, f64)> for Vec3 {
     }
 }
 
+/// Fails unless `lhs` is within `1e-9` of `rhs`
+#[cfg(test)]
+pub fn assert_near(lhs: Vec3, rhs: Vec3) {
+    assert_within(lhs, rhs, 1e-9);
+}
+
+/// Fails unless `lhs` is within `tolerance` of `rhs`
+#[cfg(test)]
+pub fn assert_within(lhs: Vec3, rhs: Vec3, tolerance: f64) {
+    assert!((lhs - rhs).length() < tolerance, "{:?} != {:?}", lhs, rhs);
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -402,11 +460,25 @@ mod test {
         assert_eq!(expected, v3.unit_vector())
     }
 
+    #[test]
+    fn perpendiculars_are_orthonormal() {
+        for v in [Vec3::i(), Vec3::new(1., 2., 3.), Vec3::new(-0.1, 0., 5.)] {
+            let (a, b) = v.perpendiculars();
+
+            assert!((a.length() - 1.).abs() < 1e-12);
+            assert!((b.length() - 1.).abs() < 1e-12);
+            assert!(a.dot(b).abs() < 1e-12);
+            assert!(a.dot(v).abs() < 1e-12);
+            assert!(b.dot(v).abs() < 1e-12);
+        }
+    }
+
     #[test]
     fn can_check_near_zero() {
         let eps = f64::EPSILON;
         assert!(Vec3::new(eps, eps, eps).near_zero());
         assert!(!Vec3::new(1.0, eps, eps).near_zero());
+        assert!(!Vec3::new(-1.0, -1.0, -1.0).near_zero());
     }
 
     #[test]
@@ -418,6 +490,22 @@ mod test {
         assert_eq!(v.reflect(n), expected);
     }
 
+    #[test]
+    fn can_take_component_wise_min_and_max() {
+        let a = Vec3::new(1., 5., -3.);
+        let b = Vec3::new(2., -5., -4.);
+
+        assert_eq!(a.min(b), Vec3::new(1., -5., -4.));
+        assert_eq!(a.max(b), Vec3::new(2., 5., -3.));
+    }
+
+    #[test]
+    fn can_index_vec3_by_axis() {
+        let v3 = Vec3::new(1., 2., 3.);
+
+        assert_eq!([v3[0], v3[1], v3[2]], [1., 2., 3.]);
+    }
+
     #[test]
     fn can_construct_vec3_from_tuple() {
         let from_tuple: Vec3 = (1., 2., 3.).into();
@@ -0,0 +1,8 @@
+#![allow(clippy::new_ret_no_self)]
+
+pub mod geometry;
+pub mod material;
+pub mod output;
+pub mod renderer;
+pub mod sampler;
+pub mod scene;
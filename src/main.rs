@@ -1,10 +1,13 @@
-mod geometry;
-mod material;
-mod renderer;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 
-use std::{fs::File, path::PathBuf};
-
-use clap::{IntoApp, Parser};
+use clap::{ArgEnum, IntoApp, Parser};
+use rust_ray_tracer::output::{self, ToneMapOperator, ToneMapping};
+use rust_ray_tracer::renderer::{
+    self, Aov, Checkpoint, Denoiser, Filter, FilterKind, World, MAX_RADIUS,
+};
+use rust_ray_tracer::sampler::SamplerKind;
+use rust_ray_tracer::scene::SceneDescription;
 
 #[derive(Parser)]
 #[clap(author, version)]
@@ -13,25 +16,113 @@ struct Cli {
     #[clap(parse(try_from_str=valid_file))]
     file_name: PathBuf,
 
+    #[clap(short = 'S', long)]
+    #[clap(parse(from_os_str))]
+    #[clap(help = "JSON scene description to render instead of the default scene")]
+    scene: Option<PathBuf>,
+
     #[clap(short = 'W')]
-    #[clap(default_value_t = 400)]
-    #[clap(help = "Width of the rendered image")]
-    image_width: u32,
+    #[clap(help = "Width of the rendered image [default: 400, or the scene's setting]")]
+    image_width: Option<u32>,
 
     #[clap(short = 'H')]
-    #[clap(default_value_t = 225)]
-    #[clap(help = "Height of the rendered image")]
-    image_height: u32,
+    #[clap(help = "Height of the rendered image [default: 225, or the scene's setting]")]
+    image_height: Option<u32>,
 
     #[clap(short = 's')]
-    #[clap(default_value_t = 100)]
-    #[clap(help = "Number of samples taken for each pixel")]
-    samples_per_pixel: usize,
+    #[clap(help = "Number of samples taken for each pixel [default: 100, or the scene's setting]")]
+    samples_per_pixel: Option<usize>,
+
+    #[clap(long)]
+    #[clap(help = "Relative error at which a pixel stops being sampled, up to -s [default: off]")]
+    noise_threshold: Option<f64>,
+
+    #[clap(long)]
+    #[clap(help = "Samples taken for each pixel before it can stop [default: 16]")]
+    min_samples: Option<usize>,
+
+    #[clap(long)]
+    #[clap(parse(try_from_str=valid_file))]
+    #[clap(help = "Also write a heat map of the samples taken for each pixel to this file")]
+    sample_map: Option<PathBuf>,
+
+    #[clap(long = "aov", value_name = "KIND=FILE", multiple_occurrences = true)]
+    #[clap(parse(try_from_str=aov_output))]
+    #[clap(
+        help = "Also write an auxiliary buffer to a file: albedo, normal, depth, object-id, material-id or sample-count"
+    )]
+    aovs: Vec<(Aov, PathBuf)>,
+
+    #[clap(long)]
+    #[clap(
+        help = "Smooth out noise guided by the albedo, normal and depth of what each pixel sees"
+    )]
+    denoise: bool,
+
+    #[clap(long, value_name = "SAMPLES")]
+    #[clap(help = "Render in passes of this many samples per pixel, saving the image after each")]
+    progressive: Option<usize>,
+
+    #[clap(long)]
+    #[clap(parse(try_from_str=valid_file))]
+    #[clap(help = "Where progressive passes are saved [default: the output file]")]
+    preview: Option<PathBuf>,
+
+    #[clap(long)]
+    #[clap(parse(from_os_str))]
+    #[clap(help = "Save the render's progress to this file after every pass")]
+    checkpoint: Option<PathBuf>,
+
+    #[clap(long, requires = "checkpoint")]
+    #[clap(help = "Carry on from the render saved in the --checkpoint file")]
+    resume: bool,
 
     #[clap(short = 'r')]
-    #[clap(default_value_t = 50)]
-    #[clap(help = "Maximum reflections per ray")]
-    max_recursion_depth: usize,
+    #[clap(help = "Maximum reflections per ray [default: 50, or the scene's setting]")]
+    max_recursion_depth: Option<usize>,
+
+    #[clap(long, value_name = "BOUNCES")]
+    #[clap(
+        help = "Bounces before paths may be ended early by Russian roulette [default: 5, or the scene's setting]"
+    )]
+    roulette_depth: Option<usize>,
+
+    #[clap(short = 'j', long)]
+    #[clap(help = "Number of worker threads to render with [default: all cores]")]
+    threads: Option<usize>,
+
+    #[clap(long)]
+    #[clap(help = "Seed for the random number generator, for reproducible renders")]
+    seed: Option<u64>,
+
+    #[clap(long, arg_enum)]
+    #[clap(
+        help = "How to map bright values into 8-bit images [default: clamp, or the scene's setting]"
+    )]
+    tonemap: Option<ToneMapOperator>,
+
+    #[clap(long, arg_enum)]
+    #[clap(
+        help = "Where the numbers behind each sample come from [default: independent, or the scene's setting]"
+    )]
+    sampler: Option<SamplerKind>,
+
+    #[clap(long, arg_enum)]
+    #[clap(
+        help = "How samples are weighted into nearby pixels [default: box, or the scene's setting]"
+    )]
+    filter: Option<FilterKind>,
+
+    #[clap(long, value_name = "PIXELS")]
+    #[clap(parse(try_from_str=positive_radius))]
+    #[clap(help = "How far samples reach for the --filter [default: depends on the filter]")]
+    filter_radius: Option<f64>,
+
+    #[clap(long, allow_hyphen_values = true)]
+    #[clap(
+        help = "Exposure adjustment in stops for 8-bit images [default: 0, or the scene's setting]"
+    )]
+    exposure: Option<f64>,
 
     #[clap(short = 'F', long)]
     #[clap(help = "Force overwrite of existing file")]
@@ -49,12 +140,33 @@ fn valid_file(s: &str) -> Result<PathBuf, String> {
             Some(ext) if ext.to_lowercase() == "pnm" => Ok(file),
             Some(ext) if ext.to_lowercase() == "bmp" => Ok(file),
             Some(ext) if ext.to_lowercase() == "tiff" => Ok(file),
+            Some(ext) if ext.to_lowercase() == "exr" => Ok(file),
+            Some(ext) if ext.to_lowercase() == "hdr" => Ok(file),
+            Some(ext) if ext.to_lowercase() == "pfm" => Ok(file),
             Some(ext) => Err(format!("The {} is not supported!", ext)),
             None => Err(format!("Could not convert {:?} to a string!?", os_str)),
         },
     }
 }
 
+fn aov_output(s: &str) -> Result<(Aov, PathBuf), String> {
+    let (kind, file) = s
+        .split_once('=')
+        .ok_or_else(|| format!("{} is not of the form KIND=FILE", s))?;
+    Ok((Aov::from_str(kind, true)?, valid_file(file)?))
+}
+
+fn positive_radius(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(radius) if radius > 0. && radius <= MAX_RADIUS => Ok(radius),
+        Ok(_) => Err(format!(
+            "{} is not a radius between 0 and {} pixels",
+            s, MAX_RADIUS
+        )),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -70,10 +182,17 @@ fn main() {
         .exit();
     }
 
+    let scene = match &cli.scene {
+        Some(path) => SceneDescription::load(path).unwrap_or_else(|err| {
+            let mut cmd = Cli::command();
+            cmd.error(clap::ErrorKind::ValueValidation, err).exit()
+        }),
+        None => SceneDescription::default(),
+    };
+
     if let Err(err) = File::options()
         .create(true)
         .append(true)
-        .write(true)
         .open(&cli.file_name)
     {
         let mut cmd = Cli::command();
@@ -84,11 +203,114 @@ fn main() {
         .exit();
     }
 
-    let imgbuf = renderer::Renderer::new()
-        .image_width(cli.image_width)
-        .image_height(cli.image_height)
-        .samples_per_pixle(cli.samples_per_pixel)
-        .max_depth(cli.max_recursion_depth)
-        .render();
-    imgbuf.save(cli.file_name).unwrap();
+    let settings = scene.settings;
+    let mut renderer = renderer::Renderer::new();
+    if let Some(threads) = cli.threads {
+        renderer = renderer.threads(threads);
+    }
+    if let Some(seed) = cli.seed {
+        renderer = renderer.seed(seed);
+    }
+    if let Some(noise_threshold) = cli.noise_threshold.or(settings.noise_threshold) {
+        renderer = renderer.noise_threshold(noise_threshold);
+    }
+    let samples_per_pixel = cli.samples_per_pixel.unwrap_or(settings.samples_per_pixel);
+    if let Some(samples_per_pass) = cli.progressive {
+        renderer = renderer.samples_per_pass(samples_per_pass);
+    } else if cli.checkpoint.is_some() {
+        // A single pass would only be saved once it's already finished
+        renderer = renderer.samples_per_pass(samples_per_pixel.min(16));
+    }
+    let tone_mapping = ToneMapping {
+        operator: cli.tonemap.unwrap_or(settings.tone_mapping.operator),
+        exposure: cli.exposure.unwrap_or(settings.tone_mapping.exposure),
+        ..settings.tone_mapping
+    };
+    // A radius from the scene is for the scene's filter, not one picked on the command line
+    let filter = match cli.filter {
+        Some(kind) => Filter {
+            kind,
+            radius: cli.filter_radius,
+        },
+        None => Filter {
+            radius: cli.filter_radius.or(settings.filter.radius),
+            ..settings.filter
+        },
+    };
+    let preview = cli.preview.as_ref().unwrap_or(&cli.file_name);
+    renderer = renderer
+        .image_width(cli.image_width.unwrap_or(settings.image_width))
+        .image_height(cli.image_height.unwrap_or(settings.image_height))
+        .samples_per_pixle(samples_per_pixel)
+        .min_samples_per_pixle(cli.min_samples.unwrap_or(settings.min_samples_per_pixel))
+        .max_depth(cli.max_recursion_depth.unwrap_or(settings.max_depth))
+        .roulette_depth(cli.roulette_depth.unwrap_or(settings.roulette_depth))
+        .sampler(cli.sampler.unwrap_or(settings.sampler))
+        .filter(filter);
+    if let Some(checkpoint) = &cli.checkpoint {
+        if cli.resume {
+            let saved = Checkpoint::load(checkpoint, &scene).unwrap_or_else(|err| {
+                let mut cmd = Cli::command();
+                cmd.error(clap::ErrorKind::ValueValidation, err).exit()
+            });
+            renderer = renderer.resume_from(saved);
+        }
+        renderer = renderer.checkpoint(checkpoint.clone());
+    }
+    let world = World::new(scene);
+    let aovs = (cli.denoise || !cli.aovs.is_empty()).then(|| renderer.render_aovs(&world));
+    // Denoising works on the linear radiance, before it's tone mapped
+    let denoise = |image: &renderer::ImageBuffer| match aovs.as_ref().filter(|_| cli.denoise) {
+        Some(aovs) => Denoiser::default().denoise(image, aovs),
+        None => image.clone(),
+    };
+    // Rendered before the image so they're there to look at while it renders
+    for (aov, path) in &cli.aovs {
+        if let Some(buffer) = aovs.as_ref().and_then(|aovs| aovs.get(*aov)) {
+            save_aov(buffer, path, *aov);
+        }
+    }
+    let (imgbuf, sample_counts) = renderer.render_progressive(&world, |image, _| {
+        // A failed preview isn't worth throwing the rest of the render away for
+        if let Err(err) = output::save(&denoise(image), preview, &tone_mapping) {
+            eprintln!("Could not write preview {:?}: {}", preview, err);
+        }
+    });
+    if let Err(err) = output::save(&denoise(&imgbuf), &cli.file_name, &tone_mapping) {
+        let mut cmd = Cli::command();
+        cmd.error(
+            clap::ErrorKind::Io,
+            format!("Could not write {:?}: {}", cli.file_name, err),
+        )
+        .exit();
+    }
+
+    for (aov, path) in &cli.aovs {
+        if *aov == Aov::SampleCount {
+            save_aov(&output::sample_counts(&sample_counts), path, *aov);
+        }
+    }
+
+    if let Some(sample_map) = &cli.sample_map {
+        let heat_map = output::sample_heat_map(&sample_counts, samples_per_pixel as u32);
+        if let Err(err) = output::save(&heat_map, sample_map, &ToneMapping::default()) {
+            let mut cmd = Cli::command();
+            cmd.error(
+                clap::ErrorKind::Io,
+                format!("Could not write {:?}: {}", sample_map, err),
+            )
+            .exit();
+        }
+    }
+}
+
+fn save_aov(buffer: &renderer::ImageBuffer, path: &Path, aov: Aov) {
+    if let Err(err) = output::save_data(buffer, path, aov.range()) {
+        let mut cmd = Cli::command();
+        cmd.error(
+            clap::ErrorKind::Io,
+            format!("Could not write {:?}: {}", path, err),
+        )
+        .exit();
+    }
 }
@@ -1,6 +1,5 @@
-use rand::Rng;
-
 use crate::geometry::Vec3;
+use crate::sampler::Sampler;
 
 use super::{Color, HitRecord, Material, MaterialEnum, Ray};
 
@@ -15,14 +14,18 @@ impl Dialectric {
             index_of_refraction,
         })
     }
+
+    pub(super) fn id(&self) -> u64 {
+        super::parameters_id(0, &[self.index_of_refraction])
+    }
 }
 
 impl Material for Dialectric {
-    fn scatter(
+    fn scatter<S: Sampler + ?Sized>(
         &self,
         ray: &Ray,
         hit_record: &HitRecord,
-        rng: &mut rand::prelude::ThreadRng,
+        sampler: &mut S,
     ) -> Option<(Color, Ray)> {
         let refraction_ratio = if hit_record.front_face {
             1.0 / self.index_of_refraction
@@ -36,9 +39,8 @@ impl Material for Dialectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let dist = rand::distributions::Uniform::new_inclusive(0.0, 1.0);
         let direction =
-            if cannot_refract || reflectance(cos_theta, refraction_ratio) > rng.sample(dist) {
+            if cannot_refract || reflectance(cos_theta, refraction_ratio) > sampler.get_1d() {
                 Vec3::reflect(unit_direction, hit_record.normal)
             } else {
                 Vec3::refract(unit_direction, hit_record.normal, refraction_ratio)
@@ -0,0 +1,33 @@
+use super::{Color, HitRecord, Material, MaterialEnum, Ray};
+use crate::sampler::Sampler;
+
+/// A surface that glows with the same radiance in every direction and reflects nothing
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> MaterialEnum {
+        MaterialEnum::DiffuseLight(Self { emit })
+    }
+
+    pub(super) fn id(&self) -> u64 {
+        super::parameters_id(1, &[self.emit.x, self.emit.y, self.emit.z])
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter<S: Sampler + ?Sized>(
+        &self,
+        _ray: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut S,
+    ) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        self.emit
+    }
+}
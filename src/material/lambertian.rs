@@ -1,6 +1,8 @@
-use rand::prelude::ThreadRng;
+use std::f64::consts::PI;
 
 use super::{Color, HitRecord, Material, MaterialEnum, Ray};
+use crate::geometry::Vec3;
+use crate::sampler::Sampler;
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct Lambertian {
@@ -11,21 +13,37 @@ impl Lambertian {
     pub fn new(albedo: Color) -> MaterialEnum {
         MaterialEnum::Lambertian(Self { albedo })
     }
+
+    pub(super) fn id(&self) -> u64 {
+        super::parameters_id(2, &[self.albedo.x, self.albedo.y, self.albedo.z])
+    }
 }
 
 impl Material for Lambertian {
-    fn scatter(
+    fn scatter<S: Sampler + ?Sized>(
         &self,
         _ray: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        sampler: &mut S,
     ) -> Option<(Color, Ray)> {
-        let scatter_direction =
-            match hit_record.normal + hit_record.normal.random_unit_vector_in_direction(rng) {
-                x if x.near_zero() => hit_record.normal,
-                x => x,
-            };
+        // A point on the unit sphere touching the surface gives a cosine weighted direction
+        let scatter_direction = match hit_record.normal + Vec3::random_unit_vector(sampler) {
+            x if x.near_zero() => hit_record.normal,
+            x => x,
+        };
         let scattered = Ray::new(hit_record.point, scatter_direction);
         Some((self.albedo, scattered))
     }
+
+    fn evaluate(&self, _ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.albedo * hit_record.normal.dot(direction).max(0.) / PI
+    }
+
+    fn pdf(&self, _ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Option<f64> {
+        Some(hit_record.normal.dot(direction).max(0.) / PI)
+    }
+
+    fn albedo(&self) -> Color {
+        self.albedo
+    }
 }
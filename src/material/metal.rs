@@ -1,6 +1,6 @@
 use super::{Material, MaterialEnum};
 use crate::geometry::{Color, HitRecord, Ray, Vec3};
-use rand::prelude::ThreadRng;
+use crate::sampler::Sampler;
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct Metal {
@@ -13,19 +13,24 @@ impl Metal {
         let roughness = roughness.map(|x| x.clamp(0.0, 1.0));
         MaterialEnum::Metal(Self { albedo, roughness })
     }
+
+    /// No roughness reflects the same as a roughness of zero
+    pub(super) fn id(&self) -> u64 {
+        let roughness = self.roughness.unwrap_or(0.);
+        super::parameters_id(3, &[self.albedo.x, self.albedo.y, self.albedo.z, roughness])
+    }
 }
 
 impl Material for Metal {
-    fn scatter(
+    fn scatter<S: Sampler + ?Sized>(
         &self,
         ray: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        sampler: &mut S,
     ) -> Option<(Color, Ray)> {
-        let fuzz = self.roughness.map_or_else(
-            || Vec3::zeros(),
-            |roughness| roughness * Vec3::random_unit_vector(rng),
-        );
+        let fuzz = self.roughness.map_or_else(Vec3::zeros, |roughness| {
+            roughness * Vec3::random_unit_vector(sampler)
+        });
         let reflected = ray.direction.reflect(hit_record.normal).unit_vector() + fuzz;
         if reflected.dot(hit_record.normal) > 0.0 {
             Some((self.albedo, Ray::new(hit_record.point, reflected)))
@@ -33,4 +38,8 @@ impl Material for Metal {
             None
         }
     }
+
+    fn albedo(&self) -> Color {
+        self.albedo
+    }
 }
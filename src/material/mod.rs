@@ -1,5 +1,5 @@
-use crate::geometry::{Color, HitRecord, Ray};
-use rand::rngs::ThreadRng;
+use crate::geometry::{Color, HitRecord, Ray, Vec3};
+use crate::sampler::{self, Sampler};
 
 mod lambertian;
 pub use lambertian::Lambertian;
@@ -7,34 +7,124 @@ mod metal;
 pub use metal::Metal;
 mod dialectric;
 pub use dialectric::Dialectric;
+mod diffuse_light;
+pub use diffuse_light::DiffuseLight;
 
 #[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub enum MaterialEnum {
     Dialectric(Dialectric),
+    DiffuseLight(DiffuseLight),
     Lambertian(Lambertian),
     Metal(Metal),
 }
 
+impl MaterialEnum {
+    /// Whether surfaces made of this are worth aiming shadow rays at
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, MaterialEnum::DiffuseLight(_))
+    }
+
+    /// The same number for every material of the same kind with the same parameters, so
+    /// copies of a material count as one in the material ID AOV
+    pub fn id(&self) -> u64 {
+        match self {
+            MaterialEnum::Dialectric(x) => x.id(),
+            MaterialEnum::DiffuseLight(x) => x.id(),
+            MaterialEnum::Lambertian(x) => x.id(),
+            MaterialEnum::Metal(x) => x.id(),
+        }
+    }
+}
+
+/// Mixes the kind of material with the bits of each of its parameters
+fn parameters_id(kind: u64, parameters: &[f64]) -> u64 {
+    parameters
+        .iter()
+        .fold(sampler::mix(kind), |id, x| sampler::mix(id ^ x.to_bits()))
+}
+
 impl Material for MaterialEnum {
-    fn scatter(
+    fn scatter<S: Sampler + ?Sized>(
         &self,
         ray: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        sampler: &mut S,
     ) -> Option<(Color, Ray)> {
         match self {
-            MaterialEnum::Dialectric(x) => x.scatter(ray, hit_record, rng),
-            MaterialEnum::Lambertian(x) => x.scatter(ray, hit_record, rng),
-            MaterialEnum::Metal(x) => x.scatter(ray, hit_record, rng),
+            MaterialEnum::Dialectric(x) => x.scatter(ray, hit_record, sampler),
+            MaterialEnum::DiffuseLight(x) => x.scatter(ray, hit_record, sampler),
+            MaterialEnum::Lambertian(x) => x.scatter(ray, hit_record, sampler),
+            MaterialEnum::Metal(x) => x.scatter(ray, hit_record, sampler),
+        }
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        match self {
+            MaterialEnum::Dialectric(x) => x.emitted(hit_record),
+            MaterialEnum::DiffuseLight(x) => x.emitted(hit_record),
+            MaterialEnum::Lambertian(x) => x.emitted(hit_record),
+            MaterialEnum::Metal(x) => x.emitted(hit_record),
+        }
+    }
+
+    fn evaluate(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        match self {
+            MaterialEnum::Dialectric(x) => x.evaluate(ray, hit_record, direction),
+            MaterialEnum::DiffuseLight(x) => x.evaluate(ray, hit_record, direction),
+            MaterialEnum::Lambertian(x) => x.evaluate(ray, hit_record, direction),
+            MaterialEnum::Metal(x) => x.evaluate(ray, hit_record, direction),
+        }
+    }
+
+    fn pdf(&self, ray: &Ray, hit_record: &HitRecord, direction: Vec3) -> Option<f64> {
+        match self {
+            MaterialEnum::Dialectric(x) => x.pdf(ray, hit_record, direction),
+            MaterialEnum::DiffuseLight(x) => x.pdf(ray, hit_record, direction),
+            MaterialEnum::Lambertian(x) => x.pdf(ray, hit_record, direction),
+            MaterialEnum::Metal(x) => x.pdf(ray, hit_record, direction),
+        }
+    }
+
+    fn albedo(&self) -> Color {
+        match self {
+            MaterialEnum::Dialectric(x) => x.albedo(),
+            MaterialEnum::DiffuseLight(x) => x.albedo(),
+            MaterialEnum::Lambertian(x) => x.albedo(),
+            MaterialEnum::Metal(x) => x.albedo(),
         }
     }
 }
 
 pub trait Material {
-    fn scatter(
+    fn scatter<S: Sampler + ?Sized>(
         &self,
         ray: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        sampler: &mut S,
     ) -> Option<(Color, Ray)>;
+
+    /// Light given off by the surface itself, which most materials don't
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::zeros()
+    }
+
+    /// Fraction of the light arriving along the unit vector `direction` that leaves back along
+    /// `ray`, including the cosine term, so that for a direction picked by `scatter` it's the
+    /// attenuation times `pdf`.
+    fn evaluate(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Color {
+        Color::zeros()
+    }
+
+    /// Solid angle density of `scatter` picking `direction`, or `None` for mirror-like
+    /// materials that only scatter into a handful of directions, where sampling the lights
+    /// can't help.
+    fn pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Option<f64> {
+        None
+    }
+
+    /// The surface's overall color, as denoisers and compositing want it. Clear and glowing
+    /// surfaces count as white.
+    fn albedo(&self) -> Color {
+        Color::ones()
+    }
 }
@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageResult, Rgb};
+
+use crate::renderer::{ImageBuffer, SampleCountBuffer};
+
+mod tonemap;
+pub use tonemap::{ToneMapOperator, ToneMapping};
+
+/// Write a rendered image, choosing the format from the file's extension. EXR, Radiance HDR and
+/// PFM keep the linear radiance as it is, everything else is tone mapped down to 8 bits.
+pub fn save(image: &ImageBuffer, path: &Path, tone_mapping: &ToneMapping) -> ImageResult<()> {
+    match save_float(image, path) {
+        Some(result) => result,
+        None => to_display(image, tone_mapping).save(path),
+    }
+}
+
+/// The values a buffer of data, rather than of light, holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataRange {
+    /// Within `[0, 1]` already
+    Unit,
+    /// Within `[-1, 1]`, like the components of a normal
+    Signed,
+    /// Anything from zero up, like distances or counts
+    Unbounded,
+}
+
+/// Write a buffer of data such as an AOV. Float formats keep the values as they are, everything
+/// else gets them mapped linearly from `range` onto 8 bits, with unbounded values scaled so the
+/// largest is white.
+pub fn save_data(image: &ImageBuffer, path: &Path, range: DataRange) -> ImageResult<()> {
+    match save_float(image, path) {
+        Some(result) => result,
+        None => data_to_display(image, range).save(path),
+    }
+}
+
+/// Sample counts as a float image, with every channel holding the count
+pub fn sample_counts(samples: &SampleCountBuffer) -> ImageBuffer {
+    ImageBuffer::from_fn(samples.width(), samples.height(), |x, y| {
+        Rgb([samples.get_pixel(x, y).0[0] as f32; 3])
+    })
+}
+
+/// Save `image` as it is if the file's extension is for a float format
+fn save_float(image: &ImageBuffer, path: &Path) -> Option<ImageResult<()>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("hdr") => Some(save_hdr(image, path)),
+        Some("pfm") => Some(save_pfm(image, path)),
+        Some("exr") => Some(image.save(path)),
+        _ => None,
+    }
+}
+
+/// Color each pixel by the fraction of `max_samples` it took, going from black through red
+/// and yellow to white
+pub fn sample_heat_map(samples: &SampleCountBuffer, max_samples: u32) -> ImageBuffer {
+    ImageBuffer::from_fn(samples.width(), samples.height(), |x, y| {
+        let fraction = samples.get_pixel(x, y).0[0] as f32 / max_samples.max(1) as f32;
+        Rgb([0., 1., 2.].map(|offset| (3. * fraction - offset).clamp(0., 1.)))
+    })
+}
+
+fn save_hdr(image: &ImageBuffer, path: &Path) -> ImageResult<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let pixels = image.pixels().copied().collect::<Vec<_>>();
+    HdrEncoder::new(file).encode(&pixels, image.width() as usize, image.height() as usize)
+}
+
+/// Portable float map: a small text header, then little-endian floats with the bottom row first
+fn save_pfm(image: &ImageBuffer, path: &Path) -> ImageResult<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_pfm(image, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn write_pfm<W: Write>(image: &ImageBuffer, writer: &mut W) -> std::io::Result<()> {
+    // A negative scale marks the data as little-endian
+    write!(writer, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    for row in image.rows().rev() {
+        for value in row.flat_map(|pixel| pixel.0) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn to_display(image: &ImageBuffer, tone_mapping: &ToneMapping) -> image::RgbImage {
+    image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgb(channels) = *image.get_pixel(x, y);
+        Rgb(channels.map(|value| {
+            let encoded = tone_mapping.apply(value as f64);
+            (encoded * u8::MAX as f64).round() as u8
+        }))
+    })
+}
+
+fn data_to_display(image: &ImageBuffer, range: DataRange) -> image::RgbImage {
+    let scale = match range {
+        DataRange::Unbounded => image
+            .pixels()
+            .flat_map(|pixel| pixel.0)
+            .filter(|value| value.is_finite())
+            .fold(0f32, f32::max),
+        _ => 1.,
+    };
+    image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgb(channels) = *image.get_pixel(x, y);
+        Rgb(channels.map(|value| {
+            let unit = match range {
+                DataRange::Unit => value,
+                DataRange::Signed => 0.5 * value + 0.5,
+                DataRange::Unbounded if scale > 0. => value / scale,
+                DataRange::Unbounded => 0.,
+            };
+            (unit.clamp(0., 1.) * u8::MAX as f32).round() as u8
+        }))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn gradient() -> ImageBuffer {
+        ImageBuffer::from_fn(3, 2, |x, y| Rgb([x as f32 * 4., y as f32, 0.25]))
+    }
+
+    #[test]
+    fn pfm_starts_with_the_bottom_row() {
+        let mut bytes = Vec::new();
+        write_pfm(&gradient(), &mut bytes).unwrap();
+
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let floats = bytes[header.len()..]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(floats.len(), 3 * 2 * 3);
+        assert_eq!(&floats[..6], &[0., 1., 0.25, 4., 1., 0.25]);
+    }
+
+    #[test]
+    fn exr_keeps_values_above_one() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_output.exr");
+        save(&gradient(), &path, &ToneMapping::default()).unwrap();
+
+        let loaded = image::open(&path).unwrap().into_rgb32f();
+
+        assert_eq!(loaded, gradient());
+    }
+
+    #[test]
+    fn heat_map_goes_from_black_to_white() {
+        let samples = SampleCountBuffer::from_fn(3, 1, |x, _| image::Luma([x * 5]));
+
+        let heat_map = sample_heat_map(&samples, 10);
+
+        assert_eq!(heat_map.get_pixel(0, 0), &Rgb([0., 0., 0.]));
+        assert_eq!(heat_map.get_pixel(1, 0), &Rgb([1., 0.5, 0.]));
+        assert_eq!(heat_map.get_pixel(2, 0), &Rgb([1., 1., 1.]));
+    }
+
+    #[test]
+    fn data_is_mapped_linearly_onto_8_bits() {
+        let signed = ImageBuffer::from_fn(1, 1, |_, _| Rgb([-1., 0., 1.]));
+
+        assert_eq!(
+            data_to_display(&signed, DataRange::Signed).get_pixel(0, 0),
+            &Rgb([0, 128, 255])
+        );
+        assert_eq!(
+            data_to_display(&gradient(), DataRange::Unbounded).get_pixel(1, 1),
+            &Rgb([128, 32, 8])
+        );
+    }
+
+    #[test]
+    fn display_conversion_is_srgb_encoded() {
+        let display = to_display(&gradient(), &ToneMapping::default());
+
+        assert_eq!(display.get_pixel(0, 0), &Rgb([0, 0, 137]));
+        assert_eq!(display.get_pixel(2, 1), &Rgb([255, 255, 137]));
+    }
+}
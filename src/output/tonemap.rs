@@ -0,0 +1,171 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// How linear radiance gets squeezed into the `[0, 1]` range of a display before sRGB encoding
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, clap::ArgEnum)]
+pub enum ToneMapOperator {
+    /// Cut off everything brighter than white
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white
+    Reinhard,
+    /// Reinhard stretched so that `white_point` maps to white
+    ExtendedReinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    /// Brightness adjustment applied before the operator, in stops
+    pub exposure: f64,
+    /// Smallest radiance shown as pure white by the extended Reinhard operator
+    #[serde(deserialize_with = "positive_white_point")]
+    pub white_point: f64,
+}
+
+fn positive_white_point<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let white_point = f64::deserialize(deserializer)?;
+    if white_point > 0. && white_point.is_finite() {
+        Ok(white_point)
+    } else {
+        Err(D::Error::custom(format!(
+            "{} is not a positive white point",
+            white_point
+        )))
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0.,
+            white_point: 4.,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Map one linear channel to an sRGB encoded value in `[0, 1]`
+    pub fn apply(&self, value: f64) -> f64 {
+        let value = value.max(0.) * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneMapOperator::Clamp => value,
+            ToneMapOperator::Reinhard => value / (1. + value),
+            ToneMapOperator::ExtendedReinhard => {
+                let white_squared = self.white_point * self.white_point;
+                value * (1. + value / white_squared) / (1. + value)
+            }
+            ToneMapOperator::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+        };
+        srgb_encode(mapped.clamp(0., 1.))
+    }
+}
+
+/// The sRGB transfer function, from linear light to encoded values
+fn srgb_encode(value: f64) -> f64 {
+    if value <= 0.003_130_8 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tone_mapping(operator: ToneMapOperator) -> ToneMapping {
+        ToneMapping {
+            operator,
+            ..ToneMapping::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn srgb_matches_reference_values() {
+        assert_close(srgb_encode(0.), 0.);
+        assert_close(srgb_encode(0.002), 0.02584);
+        assert_close(srgb_encode(0.214_041_14), 0.5);
+        assert_close(srgb_encode(1.), 1.);
+    }
+
+    #[test]
+    fn operators_keep_black_and_stay_in_range() {
+        for operator in [
+            ToneMapOperator::Clamp,
+            ToneMapOperator::Reinhard,
+            ToneMapOperator::ExtendedReinhard,
+            ToneMapOperator::Aces,
+        ] {
+            let tone_mapping = tone_mapping(operator);
+            assert_close(tone_mapping.apply(0.), 0.);
+            assert_close(tone_mapping.apply(-1.), 0.);
+            for value in [0.1, 1., 10., 1000.] {
+                let mapped = tone_mapping.apply(value);
+                assert!((0.0..=1.0).contains(&mapped), "{:?}", operator);
+            }
+        }
+    }
+
+    #[test]
+    fn bright_values_keep_detail() {
+        let reinhard = tone_mapping(ToneMapOperator::Reinhard);
+        let aces = tone_mapping(ToneMapOperator::Aces);
+
+        assert!(reinhard.apply(2.) < reinhard.apply(4.));
+        assert!(aces.apply(2.) < aces.apply(4.));
+    }
+
+    #[test]
+    fn extended_reinhard_reaches_white_at_white_point() {
+        let tone_mapping = tone_mapping(ToneMapOperator::ExtendedReinhard);
+
+        assert_close(tone_mapping.apply(tone_mapping.white_point), 1.);
+    }
+
+    #[test]
+    fn non_positive_white_points_are_rejected() {
+        for white_point in ["0.0", "-2.0"] {
+            let json = format!(r#"{{ "white_point": {} }}"#, white_point);
+
+            let err = serde_json::from_str::<ToneMapping>(&json).err().unwrap();
+
+            assert!(
+                err.to_string().contains("not a positive white point"),
+                "{}",
+                err
+            );
+        }
+        let json = r#"{ "white_point": 2.0 }"#;
+        assert_eq!(
+            serde_json::from_str::<ToneMapping>(json)
+                .unwrap()
+                .white_point,
+            2.
+        );
+    }
+
+    #[test]
+    fn each_stop_doubles_exposure() {
+        let brighter = ToneMapping {
+            exposure: 1.,
+            ..ToneMapping::default()
+        };
+
+        assert_close(brighter.apply(0.1), ToneMapping::default().apply(0.2));
+    }
+}
@@ -1,19 +1,62 @@
+mod accumulator;
+mod aov;
+mod background;
 mod camera;
+mod checkpoint;
+mod denoiser;
+mod film;
+mod filter;
+mod lights;
+mod tile;
 
+pub use self::aov::{Aov, Aovs};
+pub use self::background::Background;
+pub use self::camera::CameraDescription;
+pub use self::checkpoint::{Checkpoint, CheckpointError};
+pub use self::denoiser::Denoiser;
+pub use self::filter::{Filter, FilterKind, MAX_RADIUS};
+pub use self::lights::PointLight;
+
+use self::accumulator::PixelAccumulator;
+use self::aov::{AovPixel, AOV_SAMPLES};
 use self::camera::Camera;
-use crate::geometry::{Color, HitableList, Hittable, Plane, Ray, SceneDescription, Sphere, Vec3};
-use crate::material::{Dialectric, Lambertian, Material, Metal};
-use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
+use self::film::{FilmPixel, FilmTile};
+use self::lights::Lights;
+use self::tile::Tile;
+use crate::geometry::{Bvh, Color, HitEnum, Hittable, Ray, Vec3};
+use crate::material::Material;
+use crate::sampler::{Sampler, SamplerEnum, SamplerKind};
+use crate::scene::SceneDescription;
+use indicatif::{ProgressBar, ProgressStyle};
 use rand::prelude::*;
-use rand::seq::SliceRandom;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Edge length, in pixels, of the square tiles handed out to worker threads.
+const TILE_SIZE: u32 = 16;
+
+/// Linear radiance, with nothing clamped or gamma corrected
+pub type ImageBuffer = image::Rgb32FImage;
 
-pub type ImageBuffer = image::ImageBuffer<image::Rgb<u8>, Vec<u8>>;
+/// How many samples were taken for each pixel
+pub type SampleCountBuffer = image::ImageBuffer<image::Luma<u32>, Vec<u32>>;
 
 pub struct Renderer {
     image_width: u32,
     image_height: u32,
     samples_per_pixle: usize,
+    min_samples_per_pixle: usize,
+    noise_threshold: Option<f64>,
+    samples_per_pass: Option<usize>,
     max_depth: usize,
+    roulette_depth: usize,
+    threads: usize,
+    seed: u64,
+    sampler: SamplerKind,
+    filter: Filter,
+    checkpoint: Option<PathBuf>,
+    resume: Option<(Vec<PixelAccumulator>, Vec<FilmPixel>)>,
 }
 
 impl Renderer {
@@ -22,7 +65,17 @@ impl Renderer {
             image_width: 256,
             image_height: 256,
             samples_per_pixle: 600,
+            min_samples_per_pixle: 16,
+            noise_threshold: None,
+            samples_per_pass: None,
             max_depth: 50,
+            roulette_depth: 5,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: thread_rng().gen(),
+            sampler: SamplerKind::default(),
+            filter: Filter::default(),
+            checkpoint: None,
+            resume: None,
         }
     }
 
@@ -41,53 +94,108 @@ impl Renderer {
         self
     }
 
+    /// Samples taken for every pixel before checking whether it has converged, when sampling
+    /// adaptively
+    pub fn min_samples_per_pixle(mut self, min_samples_per_pixle: usize) -> Self {
+        self.min_samples_per_pixle = min_samples_per_pixle;
+        self
+    }
+
+    /// Stop sampling a pixel once the standard error of its mean luminance falls below this
+    /// fraction of the mean, taking `samples_per_pixle` as the most a pixel can get
+    pub fn noise_threshold(mut self, noise_threshold: f64) -> Self {
+        self.noise_threshold = Some(noise_threshold);
+        self
+    }
+
+    /// Render in passes that each take up to this many more samples for every pixel, instead of
+    /// finishing each pixel in one go
+    pub fn samples_per_pass(mut self, samples_per_pass: usize) -> Self {
+        self.samples_per_pass = Some(samples_per_pass.max(1));
+        self
+    }
+
     pub fn max_depth(mut self, max_depth: usize) -> Self {
         self.max_depth = max_depth;
         self
     }
 
-    fn get_scene(&self) -> (HitableList, Camera) {
-        let ground_material = Lambertian::new(Color::new(0.8, 0.8, 0.0));
-        let center_material = Lambertian::new(Color::new(0.7, 0.3, 0.3));
-        // let left_material = Metal::new(Color::new(0.8, 0.8, 0.8), Some(1.0));
-        let left_material = Dialectric::new(1.5);
-        let right_material = Metal::new(Color::new(0.8, 0.6, 0.2), Some(1.0));
-        let mut world = HitableList::new();
-        world.add(Plane::new(-0.5 * Vec3::j(), Vec3::j(), ground_material));
-        world.add(Sphere::new((0.0, 0.0, -1.0).into(), 0.5, center_material));
-        world.add(Sphere::new(
-            (-1., 0.0, -1.0).into(),
-            0.5,
-            left_material.clone(),
-        ));
-        world.add(Sphere::new((-1., 0.0, -1.0).into(), -0.4, left_material));
-        world.add(Sphere::new((1.0, 0.0, -1.0).into(), 0.5, right_material));
-        let desc = SceneDescription {
-            hittable: world,
-            camera_width: self.image_width,
-            camera_height: self.image_height,
-        };
-        println!("{}", serde_json::to_string_pretty(&desc).unwrap());
-        (
-            desc.hittable,
-            Camera::new(self.image_width, self.image_height),
-        )
+    /// Bounces every path makes before Russian roulette can end it early
+    pub fn roulette_depth(mut self, roulette_depth: usize) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Where the numbers behind each sample come from
+    pub fn sampler(mut self, sampler: SamplerKind) -> Self {
+        self.sampler = sampler;
+        self
     }
 
-    pub fn render(self) -> ImageBuffer {
-        let (world, camera) = self.get_scene();
+    /// How samples are weighted into the pixels around them
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
 
-        let mut imgbuf = ImageBuffer::new(self.image_width, self.image_height);
+    /// Save the samples taken so far to `path` after every pass
+    pub fn checkpoint(mut self, path: PathBuf) -> Self {
+        self.checkpoint = Some(path);
+        self
+    }
 
-        let num_pixels = self.image_width as u64 * self.image_height as u64;
+    /// Carry on from the samples in `checkpoint`, taking its seed, size, depths, sampler and
+    /// filter so the result matches a render that was never interrupted
+    pub fn resume_from(mut self, checkpoint: Checkpoint) -> Self {
+        self.seed = checkpoint.seed;
+        self.image_width = checkpoint.image_width;
+        self.image_height = checkpoint.image_height;
+        self.max_depth = checkpoint.max_depth;
+        self.roulette_depth = checkpoint.roulette_depth;
+        self.sampler = checkpoint.sampler;
+        self.filter = checkpoint.filter;
+        self.resume = Some((checkpoint.pixels, checkpoint.film));
+        self
+    }
+
+    pub fn render(self, scene: SceneDescription) -> ImageBuffer {
+        self.render_counting_samples(scene).0
+    }
+
+    pub fn render_counting_samples(
+        self,
+        scene: SceneDescription,
+    ) -> (ImageBuffer, SampleCountBuffer) {
+        self.render_progressive(&World::new(scene), |_, _| {})
+    }
 
-        let mut rng = thread_rng();
-        let dist = rand::distributions::Uniform::new_inclusive(-0.5, 0.5);
+    /// Render the world, showing `after_pass` the image so far after every pass but the last
+    pub fn render_progressive<F>(
+        mut self,
+        world: &World,
+        mut after_pass: F,
+    ) -> (ImageBuffer, SampleCountBuffer)
+    where
+        F: FnMut(&ImageBuffer, &SampleCountBuffer),
+    {
+        let camera = Camera::new(&world.camera, self.image_width, self.image_height);
 
-        let mut image_data = imgbuf.enumerate_pixels_mut().collect::<Vec<_>>();
-        image_data.shuffle(&mut rng);
+        let tiles = Tile::split(self.image_width, self.image_height, TILE_SIZE);
+        let samples_per_pass = self.samples_per_pass.unwrap_or(self.samples_per_pixle);
+        let passes = self.samples_per_pixle.div_ceil(samples_per_pass).max(1);
 
-        let prog_bar = ProgressBar::new(num_pixels)
+        let num_pixels = self.image_width as u64 * self.image_height as u64;
+        let prog_bar = ProgressBar::new(num_pixels * passes as u64)
             .with_style(
                 ProgressStyle::default_bar()
                     .template("[{elapsed}/{duration}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
@@ -95,58 +203,740 @@ impl Renderer {
                     .progress_chars("█▓▒░"),
             )
             .with_message("Rendering image...");
-        for (x, y, pixel) in image_data.into_iter().progress_with(prog_bar) {
-            let x = x as f64;
-            let y = (self.image_height - y) as f64;
-            let image_width = (self.image_width - 1) as f64;
-            let image_height = (self.image_height - 1) as f64;
-
-            *pixel = (std::iter::repeat_with(|| {
-                let u = (x + rng.sample(dist)) / image_width;
-                let v = (y + rng.sample(dist)) / image_height;
-
-                let ray = camera.get_ray(u, v);
-                ray_color(ray, &world, &mut rng, self.max_depth)
-            })
-            .take(self.samples_per_pixle)
-            .fold(Color::zeros(), |acc, val| acc + val)
-                / self.samples_per_pixle as f64)
-                .into();
+
+        let (mut pixels, mut film) = self.resume.take().unwrap_or_else(|| {
+            (
+                vec![PixelAccumulator::default(); num_pixels as usize],
+                vec![FilmPixel::default(); num_pixels as usize],
+            )
+        });
+        for pass in 0..passes {
+            let current = Pass {
+                camera: &camera,
+                world,
+                target_samples: ((pass + 1) * samples_per_pass).min(self.samples_per_pixle),
+            };
+            let rendered_tiles = self.render_pass(&tiles, &pixels, &film, &current, &prog_bar);
+            for (tile, accumulators, film_tile) in &rendered_tiles {
+                for ((x, y), accumulator) in tile.pixels().zip(accumulators) {
+                    pixels[self.pixel_index(x, y)] = *accumulator;
+                }
+                film_tile.write_tile(&mut film, self.image_width);
+            }
+            // Splats across tile edges are added in tile order, so the sums don't depend on
+            // which thread finished first
+            for (_, _, film_tile) in &rendered_tiles {
+                film_tile.add_margin(&mut film, self.image_width);
+            }
+            if let Some(path) = &self.checkpoint {
+                let checkpoint = Checkpoint {
+                    scene_hash: world.scene_hash,
+                    seed: self.seed,
+                    image_width: self.image_width,
+                    image_height: self.image_height,
+                    max_depth: self.max_depth,
+                    roulette_depth: self.roulette_depth,
+                    sampler: self.sampler,
+                    filter: self.filter,
+                    pixels: pixels.clone(),
+                    film: film.clone(),
+                };
+                // Losing a checkpoint isn't worth stopping the render for
+                if let Err(err) = checkpoint.save(path) {
+                    eprintln!("Could not write checkpoint {:?}: {}", path, err);
+                }
+            }
+            if pass + 1 < passes {
+                let (image, sample_counts) = self.image(&pixels, &film);
+                after_pass(&image, &sample_counts);
+            }
+        }
+        prog_bar.finish_using_style();
+
+        self.image(&pixels, &film)
+    }
+
+    /// Bring every pixel up to the pass's target, unless it's converged already. The rendered
+    /// tiles come back in the order they were given.
+    fn render_pass(
+        &self,
+        tiles: &[Tile],
+        pixels: &[PixelAccumulator],
+        film: &[FilmPixel],
+        pass: &Pass,
+        prog_bar: &ProgressBar,
+    ) -> Vec<(Tile, Vec<PixelAccumulator>, FilmTile)> {
+        self.for_each_tile(tiles, |tile| {
+            let mut film_tile = FilmTile::new(tile, &self.filter, film, self.image_width);
+            let accumulators = tile
+                .pixels()
+                .map(|(x, y)| {
+                    let mut accumulator = pixels[self.pixel_index(x, y)];
+                    self.sample_pixel(x, y, pass, &mut accumulator, &mut film_tile);
+                    accumulator
+                })
+                .collect::<Vec<_>>();
+            prog_bar.inc(tile.len() as u64);
+            (tile, accumulators, film_tile)
+        })
+    }
+
+    /// Hand the tiles out to the worker threads as they become free, and give back what
+    /// `render_tile` made of each in the order the tiles were given
+    fn for_each_tile<T, F>(&self, tiles: &[Tile], render_tile: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(Tile) -> T + Sync,
+    {
+        let next_tile = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            let workers = (0..self.threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut rendered = Vec::new();
+                        loop {
+                            let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                            let tile = match tiles.get(index) {
+                                Some(&tile) => tile,
+                                None => break,
+                            };
+                            rendered.push((index, render_tile(tile)));
+                        }
+                        rendered
+                    })
+                })
+                .collect::<Vec<_>>();
+            let mut rendered = workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>();
+            rendered.sort_by_key(|(index, _)| *index);
+            rendered.into_iter().map(|(_, tile)| tile).collect()
+        })
+    }
+
+    /// Render the auxiliary buffers for `world`, from the same first samples of each pixel the
+    /// image itself takes
+    pub fn render_aovs(&self, world: &World) -> Aovs {
+        let camera = Camera::new(&world.camera, self.image_width, self.image_height);
+        let tiles = Tile::split(self.image_width, self.image_height, TILE_SIZE);
+        let rendered = self.for_each_tile(&tiles, |tile| {
+            tile.pixels()
+                .map(|(x, y)| self.aov_pixel(x, y, &camera, world))
+                .collect::<Vec<_>>()
+        });
+        let mut pixels = vec![AovPixel::default(); self.pixel_index(0, self.image_height)];
+        for (tile, tile_pixels) in tiles.iter().zip(rendered) {
+            for ((x, y), pixel) in tile.pixels().zip(tile_pixels) {
+                pixels[self.pixel_index(x, y)] = pixel;
+            }
+        }
+        Aovs::from_pixels(self.image_width, self.image_height, &pixels)
+    }
+
+    fn aov_pixel(&self, x: u32, y: u32, camera: &Camera, world: &World) -> AovPixel {
+        let samples = self.samples_per_pixle.clamp(1, AOV_SAMPLES);
+        let mut pixel = AovPixel::default();
+        let mut hits = 0;
+        for sample in 0..samples {
+            let (ray, _, _) = self.pixel_ray(x, y, sample, camera);
+            match world.hittable.hit(&ray, 0.001..f64::INFINITY) {
+                Some(hit_record) => {
+                    pixel.albedo += hit_record.material.albedo();
+                    pixel.normal += hit_record.normal;
+                    pixel.depth += camera.depth(hit_record.point);
+                    hits += 1;
+                    if sample == 0 {
+                        pixel.object_id = aov::id_color(hit_record.object as u64);
+                        pixel.material_id = aov::id_color(hit_record.material.id());
+                    }
+                }
+                None => pixel.albedo += world.background.color(&ray).min(Color::ones()),
+            }
         }
+        pixel.albedo /= samples as f64;
+        if hits > 0 {
+            pixel.normal /= hits as f64;
+            pixel.depth /= hits as f64;
+        }
+        pixel
+    }
+
+    fn image(
+        &self,
+        pixels: &[PixelAccumulator],
+        film: &[FilmPixel],
+    ) -> (ImageBuffer, SampleCountBuffer) {
+        let imgbuf = ImageBuffer::from_fn(self.image_width, self.image_height, |x, y| {
+            film[self.pixel_index(x, y)].value().into()
+        });
+        let sample_counts =
+            SampleCountBuffer::from_fn(self.image_width, self.image_height, |x, y| {
+                image::Luma([pixels[self.pixel_index(x, y)].samples() as u32])
+            });
+        (imgbuf, sample_counts)
+    }
+
+    fn pixel_index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.image_width as usize + x as usize
+    }
+
+    /// Keep sampling the pixel until it has the pass's target, or has converged when sampling
+    /// adaptively, splatting every sample onto the film. Each sample's numbers depend only on its
+    /// index, so it makes no difference how the samples are split between passes or threads.
+    fn sample_pixel(
+        &self,
+        x: u32,
+        y: u32,
+        pass: &Pass,
+        accumulator: &mut PixelAccumulator,
+        film: &mut FilmTile,
+    ) {
+        while accumulator.samples() < pass.target_samples && !self.converged(accumulator) {
+            let (ray, offset, mut sampler) =
+                self.pixel_ray(x, y, accumulator.samples(), pass.camera);
+            let color = ray_color(
+                ray,
+                pass.world,
+                &mut sampler,
+                self.max_depth,
+                self.roulette_depth,
+            );
+            accumulator.add(color);
+            film.splat(&self.filter, x, y, offset, color);
+        }
+    }
+
+    /// The camera ray for sample `sample_index` of the pixel, how far from the pixel's center it
+    /// was jittered, and the sampler to carry on the sample's path with
+    fn pixel_ray(
+        &self,
+        x: u32,
+        y: u32,
+        sample_index: usize,
+        camera: &Camera,
+    ) -> (Ray, (f64, f64), SamplerEnum) {
+        let mut sampler = self.sampler.start(
+            self.seed,
+            self.pixel_index(x, y) as u64,
+            sample_index as u64,
+            self.samples_per_pixle,
+        );
+        let [jitter_x, jitter_y] = sampler.get_2d().map(|x| x - 0.5);
+        let column = x as f64;
+        let row = (self.image_height - y) as f64;
+        let u = (column + jitter_x) / (self.image_width - 1) as f64;
+        let v = (row + jitter_y) / (self.image_height - 1) as f64;
 
-        imgbuf
+        let ray = camera.get_ray(u, v, &mut sampler);
+        // Image rows run down while `v` runs up
+        (ray, (jitter_x, -jitter_y), sampler)
+    }
+
+    fn converged(&self, accumulator: &PixelAccumulator) -> bool {
+        self.noise_threshold.is_some_and(|noise_threshold| {
+            accumulator.samples() >= self.min_samples_per_pixle
+                && accumulator.relative_error() <= noise_threshold
+        })
     }
 }
 
-impl Into<image::Rgb<u8>> for Vec3 {
-    fn into(self) -> image::Rgb<u8> {
-        let red = self.x.clamp(0.0, 0.999).sqrt();
-        let green = self.y.clamp(0.0, 0.999).sqrt();
-        let blue = self.z.clamp(0.0, 0.999).sqrt();
+/// What every pixel of one pass is rendered with
+struct Pass<'a> {
+    camera: &'a Camera,
+    world: &'a World,
+    /// Samples each pixel is brought up to, unless it converges first
+    target_samples: usize,
+}
 
-        let conversion_nubmer = u8::MAX as f64 + 1.0;
-        let red = (red * conversion_nubmer) as u8;
-        let green = (green * conversion_nubmer) as u8;
-        let blue = (blue * conversion_nubmer) as u8;
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        image::Rgb([red, green, blue])
+impl From<Vec3> for image::Rgb<f32> {
+    fn from(color: Vec3) -> Self {
+        image::Rgb([color.x as f32, color.y as f32, color.z as f32])
     }
 }
 
-fn ray_color(ray: Ray, world: &HitableList, rng: &mut ThreadRng, depth: usize) -> Color {
-    if depth <= 0 {
-        return Color::zeros();
+/// The scene as the renderer traces it, with its objects put into a `Bvh`. Build it once to
+/// render both the image and its auxiliary buffers from.
+pub struct World {
+    camera: CameraDescription,
+    hittable: HitEnum,
+    background: Background,
+    lights: Lights,
+    /// Identifies the scene in checkpoints
+    scene_hash: u64,
+}
+
+impl World {
+    pub fn new(scene: SceneDescription) -> Self {
+        let scene_hash = checkpoint::scene_hash(&scene);
+        let hittable = Bvh::new(scene.hittable);
+        Self {
+            camera: scene.camera,
+            lights: Lights::new(&hittable, scene.lights),
+            hittable,
+            background: scene.background,
+            scene_hash,
+        }
     }
-    if let Some(hit_record) = world.hit(&ray, 0.001..f64::INFINITY) {
-        hit_record
+}
+
+/// Light arriving back along `ray`, following it for up to `max_depth` hits. After
+/// `roulette_depth` of them, paths carrying little light are ended at random, with the ones that
+/// carry on weighted up to make up for the others.
+fn ray_color<S: Sampler + ?Sized>(
+    mut ray: Ray,
+    world: &World,
+    sampler: &mut S,
+    max_depth: usize,
+    roulette_depth: usize,
+) -> Color {
+    let mut color = Color::zeros();
+    let mut throughput = Color::ones();
+    // The density the ray's direction was picked with, when it was scattered from a surface
+    // that also had the lights sampled for it
+    let mut scatter_pdf = None;
+    for depth in 0..max_depth {
+        let hit_record = match world.hittable.hit(&ray, 0.001..f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return color + throughput * world.background.color(&ray),
+        };
+
+        let mut emitted = hit_record.material.emitted(&hit_record);
+        if let Some(scatter_pdf) = scatter_pdf {
+            if emitted != Color::zeros() {
+                emitted *= world
+                    .lights
+                    .scatter_weight(ray.origin, ray.direction, scatter_pdf);
+            }
+        }
+        color += throughput * emitted;
+
+        let (attenuation, scattered) = match hit_record.material.scatter(&ray, &hit_record, sampler)
+        {
+            Some(scatter) => scatter,
+            None => break,
+        };
+        scatter_pdf = hit_record
             .material
-            .scatter(&ray, &hit_record, rng)
-            .map_or(Color::zeros(), |(attenuation, scattered)| {
-                attenuation * ray_color(scattered, &world, rng, depth - 1)
-            })
-    } else {
-        let unit_direction = ray.direction.unit_vector();
-        let t = 0.5 * (unit_direction.y + 1.);
-        (1. - t) * Color::ones() + t * Color::new(0.5, 0.7, 1.0)
+            .pdf(&ray, &hit_record, scattered.direction);
+        if scatter_pdf.is_some() {
+            color += throughput
+                * world
+                    .lights
+                    .direct(&ray, &hit_record, &world.hittable, sampler);
+        }
+        throughput = throughput * attenuation;
+
+        if depth + 1 >= roulette_depth {
+            let survival = throughput.max_component().min(1.);
+            if survival <= 0. || sampler.get_1d() >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+        ray = scattered;
+    }
+    color
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::{HitableList, Plane, Sphere};
+    use crate::material::{DiffuseLight, Lambertian, Metal};
+    use crate::scene::SceneDescription;
+
+    fn render(threads: usize, seed: u64) -> ImageBuffer {
+        Renderer::new()
+            .image_width(24)
+            .image_height(16)
+            .samples_per_pixle(4)
+            .threads(threads)
+            .seed(seed)
+            .render(SceneDescription::default())
+    }
+
+    #[test]
+    fn same_seed_renders_identically_across_thread_counts() {
+        assert!(render(1, 42) == render(3, 42));
+    }
+
+    #[test]
+    fn different_seeds_render_differently() {
+        assert!(render(2, 1) != render(2, 2));
+    }
+
+    #[test]
+    fn passes_render_the_same_image_as_one_go() {
+        let mut previews = 0;
+        let (progressive, _) = Renderer::new()
+            .image_width(24)
+            .image_height(16)
+            .samples_per_pixle(10)
+            .samples_per_pass(3)
+            .seed(5)
+            .render_progressive(&World::new(SceneDescription::default()), |_, samples| {
+                previews += 1;
+                assert!(samples.pixels().all(|count| count.0[0] == previews * 3));
+            });
+        let all_at_once = Renderer::new()
+            .image_width(24)
+            .image_height(16)
+            .samples_per_pixle(10)
+            .seed(5)
+            .render(SceneDescription::default());
+
+        assert_eq!(previews, 3);
+        assert!(progressive == all_at_once);
+    }
+
+    #[test]
+    fn resumed_render_matches_uninterrupted_render() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_resume.checkpoint");
+        let renderer = || {
+            Renderer::new()
+                .image_width(24)
+                .image_height(16)
+                .samples_per_pixle(6)
+                .samples_per_pass(2)
+                .seed(9)
+        };
+        let uninterrupted = renderer().render(SceneDescription::default());
+        // Stand in for being killed after the first pass by stopping there
+        renderer()
+            .samples_per_pixle(2)
+            .checkpoint(path.clone())
+            .render(SceneDescription::default());
+
+        let checkpoint = Checkpoint::load(&path, &SceneDescription::default()).unwrap();
+        let resumed = renderer()
+            .seed(1)
+            .resume_from(checkpoint)
+            .render(SceneDescription::default());
+
+        assert!(resumed == uninterrupted);
+    }
+
+    #[test]
+    fn wide_filters_render_identically_across_thread_counts() {
+        let render = |threads: usize| {
+            Renderer::new()
+                .image_width(40)
+                .image_height(20)
+                .samples_per_pixle(2)
+                .filter(Filter {
+                    kind: FilterKind::Lanczos,
+                    radius: None,
+                })
+                .threads(threads)
+                .seed(3)
+                .render(SceneDescription::default())
+        };
+
+        assert!(render(1) == render(4));
+    }
+
+    #[test]
+    fn filters_keep_flat_images_flat() {
+        let scene = || SceneDescription {
+            background: Background::Solid(Color::new(0.5, 0.5, 0.5)),
+            hittable: HitableList::new(),
+            ..SceneDescription::default()
+        };
+        for kind in [
+            FilterKind::Box,
+            FilterKind::Tent,
+            FilterKind::Gaussian,
+            FilterKind::Mitchell,
+            FilterKind::Lanczos,
+        ] {
+            let image = Renderer::new()
+                .image_width(20)
+                .image_height(20)
+                .samples_per_pixle(2)
+                .filter(Filter { kind, radius: None })
+                .seed(0)
+                .render(scene());
+
+            assert!(
+                image.pixels().all(|pixel| (pixel.0[1] - 0.5).abs() < 1e-5),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn adaptive_sampling_stops_early_on_flat_pixels() {
+        let scene = SceneDescription {
+            background: Background::Solid(Color::ones()),
+            hittable: HitableList::new(),
+            ..SceneDescription::default()
+        };
+        let (image, samples) = Renderer::new()
+            .image_width(4)
+            .image_height(4)
+            .samples_per_pixle(64)
+            .min_samples_per_pixle(8)
+            .noise_threshold(0.01)
+            .seed(0)
+            .render_counting_samples(scene);
+
+        assert!(samples.pixels().all(|count| count.0 == [8]));
+        assert!(image.pixels().all(|pixel| pixel.0 == [1.; 3]));
+    }
+
+    #[test]
+    fn adaptive_sampling_keeps_going_on_noisy_pixels() {
+        let (_, samples) = Renderer::new()
+            .image_width(6)
+            .image_height(4)
+            .samples_per_pixle(64)
+            .min_samples_per_pixle(8)
+            .noise_threshold(0.001)
+            .seed(0)
+            .render_counting_samples(SceneDescription::default());
+
+        assert!(samples.pixels().any(|count| count.0 == [64]));
+        assert!(samples.pixels().all(|count| (8..=64).contains(&count.0[0])));
+    }
+
+    fn render_in_the_dark(emit: Color) -> ImageBuffer {
+        let mut hittable = HitableList::new();
+        hittable.add(Sphere::new(-Vec3::k(), 0.5, DiffuseLight::new(emit)));
+        hittable.add(Sphere::new(
+            Vec3::new(0., -100.5, -1.),
+            100.,
+            Lambertian::new(Color::ones()),
+        ));
+        let scene = SceneDescription {
+            background: Background::Solid(Color::zeros()),
+            hittable,
+            ..SceneDescription::default()
+        };
+        Renderer::new()
+            .image_width(8)
+            .image_height(8)
+            .samples_per_pixle(4)
+            .seed(0)
+            .render(scene)
+    }
+
+    #[test]
+    fn aovs_describe_the_first_hit() {
+        let albedo = Color::new(0.2, 0.4, 0.6);
+        let mut hittable = HitableList::new();
+        hittable.add(Sphere::new(-2. * Vec3::k(), 0.5, Lambertian::new(albedo)));
+        hittable.add(Sphere::new(
+            -2. * Vec3::k() + Vec3::i(),
+            0.3,
+            Metal::new(albedo, None),
+        ));
+        let sky = Color::new(0.5, 1., 2.);
+        let scene = SceneDescription {
+            background: Background::Solid(sky),
+            hittable,
+            ..SceneDescription::default()
+        };
+
+        let aovs = Renderer::new()
+            .image_width(21)
+            .image_height(21)
+            .samples_per_pixle(4)
+            .seed(0)
+            .render_aovs(&World::new(scene));
+
+        let near = |lhs: &image::Rgb<f32>, rhs: Color| {
+            (Vec3::new(lhs.0[0] as f64, lhs.0[1] as f64, lhs.0[2] as f64) - rhs).length() < 0.1
+        };
+        assert!(near(aovs.albedo.get_pixel(10, 11), albedo));
+        assert!(near(aovs.normal.get_pixel(10, 11), Vec3::k()));
+        assert!(near(aovs.depth.get_pixel(10, 11), 1.5 * Color::ones()));
+        assert!(near(aovs.albedo.get_pixel(0, 0), sky.min(Color::ones())));
+        assert!(near(aovs.depth.get_pixel(0, 0), Color::zeros()));
+        assert!(near(aovs.object_id.get_pixel(0, 0), Color::zeros()));
+        let (left, right) = ((10, 11), (15, 11));
+        assert!(!near(
+            aovs.object_id.get_pixel(right.0, right.1),
+            Color::zeros()
+        ));
+        assert!(
+            aovs.object_id.get_pixel(left.0, left.1) != aovs.object_id.get_pixel(right.0, right.1)
+        );
+        assert!(
+            aovs.material_id.get_pixel(left.0, left.1)
+                != aovs.material_id.get_pixel(right.0, right.1)
+        );
+    }
+
+    #[test]
+    fn emitters_light_the_scene_without_a_sky() {
+        let image = render_in_the_dark(Color::new(4., 4., 4.));
+
+        assert!(image.pixels().any(|pixel| pixel.0[0] > 1.));
+    }
+
+    #[test]
+    fn scene_without_emitters_or_sky_is_black() {
+        let image = render_in_the_dark(Color::zeros());
+
+        assert!(image.pixels().all(|pixel| pixel.0 == [0.; 3]));
+    }
+
+    fn render_inside_a_light(albedo: f64, roulette_depth: usize) -> ImageBuffer {
+        let mut hittable = HitableList::new();
+        hittable.add(Sphere::new(
+            Vec3::zeros(),
+            10.,
+            DiffuseLight::new(Color::ones()),
+        ));
+        hittable.add(Sphere::new(
+            -Vec3::k(),
+            0.5,
+            Lambertian::new(albedo * Color::ones()),
+        ));
+        let scene = SceneDescription {
+            background: Background::Solid(Color::zeros()),
+            hittable,
+            ..SceneDescription::default()
+        };
+        Renderer::new()
+            .image_width(8)
+            .image_height(8)
+            .samples_per_pixle(64)
+            .roulette_depth(roulette_depth)
+            .seed(0)
+            .render(scene)
+    }
+
+    #[test]
+    fn diffuse_surface_inside_a_light_reflects_its_albedo() {
+        let image = render_inside_a_light(0.5, 5);
+
+        for (x, y) in [(3, 3), (3, 4), (4, 3), (4, 4)] {
+            let value = image.get_pixel(x, y).0[0];
+            assert!((value - 0.5).abs() < 0.05, "{}", value);
+        }
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_render_with_less_noise() {
+        let render = |sampler: SamplerKind, samples: usize| {
+            Renderer::new()
+                .image_width(16)
+                .image_height(8)
+                .samples_per_pixle(samples)
+                .sampler(sampler)
+                .seed(1)
+                .render(SceneDescription::default())
+        };
+        let reference = render(SamplerKind::Independent, 1024);
+        let error = |sampler: SamplerKind| {
+            render(sampler, 16)
+                .pixels()
+                .zip(reference.pixels())
+                .flat_map(|(pixel, expected)| {
+                    pixel
+                        .0
+                        .into_iter()
+                        .zip(expected.0)
+                        .map(|(a, b)| (a - b).powi(2))
+                })
+                .sum::<f32>()
+        };
+        let independent = error(SamplerKind::Independent);
+
+        for sampler in [
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            let error = error(sampler);
+            assert!(
+                error < independent,
+                "{:?}: {} >= {}",
+                sampler,
+                error,
+                independent
+            );
+        }
+    }
+
+    #[test]
+    fn russian_roulette_does_not_change_the_expected_image() {
+        let mean =
+            |image: ImageBuffer| image.pixels().map(|pixel| pixel.0[0] as f64).sum::<f64>() / 64.;
+        let without = mean(render_inside_a_light(0.5, 50));
+        let with = mean(render_inside_a_light(0.5, 0));
+
+        assert!((with - without).abs() < 0.01, "{} != {}", with, without);
+    }
+
+    #[test]
+    fn deep_paths_do_not_overflow_the_stack() {
+        let mut hittable = HitableList::new();
+        hittable.add(Sphere::new(
+            Vec3::zeros(),
+            10.,
+            Metal::new(Color::ones(), None),
+        ));
+        let scene = SceneDescription {
+            hittable,
+            ..SceneDescription::default()
+        };
+        let image = Renderer::new()
+            .image_width(2)
+            .image_height(2)
+            .samples_per_pixle(1)
+            .max_depth(100_000)
+            .roulette_depth(100_000)
+            .seed(0)
+            .render(scene);
+
+        assert!(image.pixels().all(|pixel| pixel.0 == [0.; 3]));
+    }
+
+    #[test]
+    fn point_lights_follow_the_inverse_square_law() {
+        let mut hittable = HitableList::new();
+        hittable.add(Plane::new(
+            -Vec3::k(),
+            Vec3::k(),
+            Lambertian::new(Color::ones()),
+        ));
+        let render = |distance: f64| {
+            let scene = SceneDescription {
+                background: Background::Solid(Color::zeros()),
+                lights: vec![PointLight {
+                    position: Vec3::new(0., 0., distance - 1.),
+                    intensity: Color::ones(),
+                }],
+                hittable: hittable.clone(),
+                camera: CameraDescription {
+                    vertical_fov: 1.,
+                    ..CameraDescription::default()
+                },
+                ..SceneDescription::default()
+            };
+            Renderer::new()
+                .image_width(2)
+                .image_height(2)
+                .samples_per_pixle(1)
+                .max_depth(1)
+                .seed(0)
+                .render(scene)
+                .get_pixel(0, 0)
+                .0[0]
+        };
+
+        let ratio = render(1.) / render(2.);
+
+        assert!((ratio - 4.).abs() < 0.01, "{}", ratio);
     }
 }
@@ -0,0 +1,112 @@
+use std::io::{self, Read, Write};
+
+use crate::geometry::Color;
+
+/// Running totals of the samples taken for one pixel, for judging when it has converged. The
+/// samples' colors go onto the film.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PixelAccumulator {
+    luminance_sum: f64,
+    luminance_squares: f64,
+    samples: usize,
+}
+
+impl PixelAccumulator {
+    pub fn add(&mut self, color: Color) {
+        let luminance = luminance(color);
+        self.luminance_sum += luminance;
+        self.luminance_squares += luminance * luminance;
+        self.samples += 1;
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Standard error of the mean luminance over the mean itself. Means are floored so that
+    /// black pixels count as converged.
+    pub fn relative_error(&self) -> f64 {
+        if self.samples < 2 {
+            return f64::INFINITY;
+        }
+        let samples = self.samples as f64;
+        let mean = self.luminance_sum / samples;
+        let variance =
+            ((self.luminance_squares - self.luminance_sum * mean) / (samples - 1.)).max(0.);
+        (variance / samples).sqrt() / mean.max(1e-3)
+    }
+
+    /// Write the totals exactly, as little-endian numbers
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.luminance_sum.to_le_bytes())?;
+        writer.write_all(&self.luminance_squares.to_le_bytes())?;
+        writer.write_all(&(self.samples as u64).to_le_bytes())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut next = || -> io::Result<[u8; 8]> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(bytes)
+        };
+        Ok(Self {
+            luminance_sum: f64::from_le_bytes(next()?),
+            luminance_squares: f64::from_le_bytes(next()?),
+            samples: u64::from_le_bytes(next()?) as usize,
+        })
+    }
+}
+
+/// Rec. 709 luminance of a linear color
+fn luminance(color: Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn accumulate(colors: &[Color]) -> PixelAccumulator {
+        let mut accumulator = PixelAccumulator::default();
+        colors.iter().for_each(|&color| accumulator.add(color));
+        accumulator
+    }
+
+    #[test]
+    fn samples_are_counted() {
+        let accumulator = accumulate(&[Color::ones(), Color::new(3., 1., 0.)]);
+
+        assert_eq!(accumulator.samples(), 2);
+    }
+
+    #[test]
+    fn constant_samples_have_no_error() {
+        assert_eq!(accumulate(&[Color::ones(); 4]).relative_error(), 0.);
+        assert_eq!(accumulate(&[Color::zeros(); 4]).relative_error(), 0.);
+    }
+
+    #[test]
+    fn single_sample_has_unknown_error() {
+        assert_eq!(accumulate(&[Color::ones()]).relative_error(), f64::INFINITY);
+    }
+
+    #[test]
+    fn totals_survive_writing_and_reading() {
+        let accumulator = accumulate(&[Color::new(0.1, 0.2, 0.3), Color::new(1e9, 0., 1. / 3.)]);
+        let mut bytes = Vec::new();
+        accumulator.write(&mut bytes).unwrap();
+
+        let read = PixelAccumulator::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read, accumulator);
+    }
+
+    #[test]
+    fn error_shrinks_with_more_samples() {
+        let few = accumulate(&[Color::zeros(), Color::ones()].repeat(2));
+        let many = accumulate(&[Color::zeros(), Color::ones()].repeat(20));
+
+        assert!(many.relative_error() < few.relative_error());
+    }
+}
@@ -0,0 +1,108 @@
+use super::ImageBuffer;
+use crate::geometry::Color;
+use crate::output::DataRange;
+use crate::sampler::mix;
+
+/// Samples per pixel averaged into the albedo, normal and depth buffers. They converge much
+/// faster than the beauty image, so a few go a long way.
+pub(super) const AOV_SAMPLES: usize = 16;
+
+/// An auxiliary buffer rendered alongside the image, for compositing or for guiding a denoiser
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ArgEnum)]
+pub enum Aov {
+    /// Color of the first surface each ray hits, or of the background where it misses
+    Albedo,
+    /// Shading normal at the first hit, facing the camera, with components in `[-1, 1]`
+    Normal,
+    /// Distance from the camera to the first hit along its view direction
+    Depth,
+    /// A flat color for each entry of the scene's list of objects
+    ObjectId,
+    /// A flat color for each distinct material
+    MaterialId,
+    /// How many samples were taken for each pixel
+    SampleCount,
+}
+
+impl Aov {
+    /// The values the buffer holds, for squeezing it into 8-bit images
+    pub fn range(self) -> DataRange {
+        match self {
+            Self::Albedo | Self::ObjectId | Self::MaterialId => DataRange::Unit,
+            Self::Normal => DataRange::Signed,
+            Self::Depth | Self::SampleCount => DataRange::Unbounded,
+        }
+    }
+}
+
+/// What the camera sees first through one pixel, averaged over its samples where that makes
+/// sense. IDs come from the first sample alone, since blending them gives colors of no object.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(super) struct AovPixel {
+    pub albedo: Color,
+    pub normal: Color,
+    pub depth: f64,
+    pub object_id: Color,
+    pub material_id: Color,
+}
+
+/// The auxiliary buffers for every pixel, in linear values. Pixels showing the background have
+/// no normal, depth or IDs and are left black in those.
+pub struct Aovs {
+    pub albedo: ImageBuffer,
+    pub normal: ImageBuffer,
+    pub depth: ImageBuffer,
+    pub object_id: ImageBuffer,
+    pub material_id: ImageBuffer,
+}
+
+impl Aovs {
+    pub(super) fn from_pixels(image_width: u32, image_height: u32, pixels: &[AovPixel]) -> Self {
+        let buffer = |value: &dyn Fn(&AovPixel) -> Color| {
+            ImageBuffer::from_fn(image_width, image_height, |x, y| {
+                value(&pixels[y as usize * image_width as usize + x as usize]).into()
+            })
+        };
+        Self {
+            albedo: buffer(&|pixel| pixel.albedo),
+            normal: buffer(&|pixel| pixel.normal),
+            depth: buffer(&|pixel| pixel.depth * Color::ones()),
+            object_id: buffer(&|pixel| pixel.object_id),
+            material_id: buffer(&|pixel| pixel.material_id),
+        }
+    }
+
+    /// The buffer for `aov`, or `None` for the sample count, which comes from the render itself
+    pub fn get(&self, aov: Aov) -> Option<&ImageBuffer> {
+        match aov {
+            Aov::Albedo => Some(&self.albedo),
+            Aov::Normal => Some(&self.normal),
+            Aov::Depth => Some(&self.depth),
+            Aov::ObjectId => Some(&self.object_id),
+            Aov::MaterialId => Some(&self.material_id),
+            Aov::SampleCount => None,
+        }
+    }
+}
+
+/// A bright color that only depends on `id`, so the same object gets the same color in every
+/// render
+pub(super) fn id_color(id: u64) -> Color {
+    let bits = mix(id);
+    let channel = |shift: u32| 0.2 + 0.8 * ((bits >> shift) & 0xff) as f64 / 255.;
+    Color::new(channel(0), channel(8), channel(16))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn id_colors_are_stable_and_never_black() {
+        assert_eq!(id_color(3), id_color(3));
+        assert_ne!(id_color(3), id_color(4));
+        for id in 0..100 {
+            assert!(id_color(id).x >= 0.2 && id_color(id).max_component() <= 1.);
+        }
+    }
+}
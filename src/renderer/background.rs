@@ -0,0 +1,235 @@
+use std::f64::consts::PI;
+use std::path::PathBuf;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::geometry::{Color, Model, Ray, Vec3};
+
+/// What a ray sees when it leaves the scene without hitting anything
+#[derive(Serialize, Deserialize, Clone)]
+pub enum Background {
+    /// The same color in every direction; black lights the scene only by its emissive materials
+    Solid(Color),
+    /// A blend from `bottom`, looking against `up`, to `top`, looking along it
+    Gradient {
+        bottom: Color,
+        top: Color,
+        #[serde(deserialize_with = "nonzero_up")]
+        up: Vec3,
+    },
+    Environment(EnvironmentMap),
+}
+
+fn nonzero_up<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec3, D::Error> {
+    let up = Vec3::deserialize(deserializer)?;
+    if up.near_zero() {
+        return Err(D::Error::custom("a gradient's up can't be zero"));
+    }
+    Ok(up)
+}
+
+impl Default for Background {
+    /// White at the horizon fading to light blue overhead
+    fn default() -> Self {
+        Self::Gradient {
+            bottom: Color::ones(),
+            top: Color::new(0.5, 0.7, 1.0),
+            up: Vec3::j(),
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient { bottom, top, up } => {
+                let t = 0.5 * (ray.direction.unit_vector().dot(up.unit_vector()) + 1.);
+                (1. - t) * *bottom + t * *top
+            }
+            Self::Environment(map) => map.color(ray.direction),
+        }
+    }
+
+    /// Files the background was read from, like [`Hittable::source_files`]
+    ///
+    /// [`Hittable::source_files`]: crate::geometry::Hittable::source_files
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Environment(map) => vec![map.source.clone()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// An equirectangular image surrounding the scene, with the top row straight up along `y`.
+/// Serializes as the description it was loaded from.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "EnvironmentData", into = "EnvironmentData")]
+pub struct EnvironmentMap {
+    data: EnvironmentData,
+    image: image::Rgb32FImage,
+    /// Where the image was read from
+    source: PathBuf,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnvironmentData {
+    /// Any format the image crate reads, though only HDR and EXR hold more than 8 bits.
+    /// Relative to the scene file's directory, like a model's path.
+    path: PathBuf,
+    /// Turn about the `y` axis, in degrees
+    #[serde(default)]
+    rotation: f64,
+    /// Multiplier for the image's values
+    #[serde(default = "unit_intensity")]
+    intensity: f64,
+}
+
+fn unit_intensity() -> f64 {
+    1.
+}
+
+impl EnvironmentMap {
+    fn color(&self, direction: Vec3) -> Color {
+        let direction = direction.unit_vector();
+        let azimuth = direction.x.atan2(-direction.z) / (2. * PI) + 0.5;
+        let u = (azimuth + self.data.rotation / 360.).rem_euclid(1.);
+        let v = direction.y.clamp(-1., 1.).acos() / PI;
+
+        let (width, height) = self.image.dimensions();
+        let x = ((u * width as f64) as u32).min(width - 1);
+        let y = ((v * height as f64) as u32).min(height - 1);
+        let [red, green, blue] = self.image.get_pixel(x, y).0;
+        self.data.intensity * Color::new(red as f64, green as f64, blue as f64)
+    }
+}
+
+impl TryFrom<EnvironmentData> for EnvironmentMap {
+    type Error = String;
+
+    fn try_from(data: EnvironmentData) -> Result<Self, Self::Error> {
+        let path = Model::scene_path(&data.path);
+        let image = image::open(&path)
+            .map_err(|err| format!("Could not load environment map {:?}: {}", path, err))?
+            .into_rgb32f();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(format!("Environment map {:?} is empty", path));
+        }
+        Ok(Self {
+            data,
+            image,
+            source: path,
+        })
+    }
+}
+
+impl From<EnvironmentMap> for EnvironmentData {
+    fn from(map: EnvironmentMap) -> Self {
+        map.data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Four columns of different colors, with a brighter top row
+    fn environment(rotation: f64, intensity: f64) -> EnvironmentMap {
+        let path = std::env::temp_dir().join(format!(
+            "rust_ray_tracer_environment_{}_{}.exr",
+            rotation, intensity
+        ));
+        image::Rgb32FImage::from_fn(4, 2, |x, y| image::Rgb([x as f32, (2 - y) as f32, 0.5]))
+            .save(&path)
+            .unwrap();
+        EnvironmentMap::try_from(EnvironmentData {
+            path,
+            rotation,
+            intensity,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn default_is_the_sky_gradient() {
+        let background = Background::default();
+
+        let up = background.color(&Ray::new(Vec3::zeros(), Vec3::j()));
+        let down = background.color(&Ray::new(Vec3::zeros(), -Vec3::j()));
+
+        assert_eq!(up, Color::new(0.5, 0.7, 1.0));
+        assert_eq!(down, Color::ones());
+    }
+
+    #[test]
+    fn gradient_follows_its_up_axis() {
+        let background = Background::Gradient {
+            bottom: Color::zeros(),
+            top: Color::ones(),
+            up: Vec3::i(),
+        };
+
+        let color = background.color(&Ray::new(Vec3::zeros(), Vec3::i()));
+
+        assert_eq!(color, Color::ones());
+    }
+
+    #[test]
+    fn environment_looks_up_directions() {
+        let map = environment(0., 2.);
+
+        assert_eq!(map.color(Vec3::new(0., 1., -0.1)), Color::new(4., 4., 1.));
+        assert_eq!(map.color(Vec3::new(0., -1., -0.1)), Color::new(4., 2., 1.));
+        assert_eq!(map.color(Vec3::new(1., 0.1, 0.)), Color::new(6., 4., 1.));
+    }
+
+    #[test]
+    fn environment_rotation_turns_about_y() {
+        let map = environment(90., 1.);
+
+        assert_eq!(map.color(Vec3::new(0., 0.1, -1.)), Color::new(3., 2., 0.5));
+    }
+
+    #[test]
+    fn zero_gradient_up_is_rejected() {
+        let json = r#"{ "Gradient": {
+            "bottom": { "x": 1.0, "y": 1.0, "z": 1.0 },
+            "top": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "up": { "x": 0.0, "y": 0.0, "z": 0.0 }
+        } }"#;
+
+        let err = serde_json::from_str::<Background>(json).err().unwrap();
+
+        assert!(err.to_string().contains("up can't be zero"), "{}", err);
+    }
+
+    #[test]
+    fn environment_paths_are_relative_to_the_scene_directory() {
+        let name = format!("rust_ray_tracer_relative_{}.exr", std::process::id());
+        image::Rgb32FImage::from_pixel(2, 1, image::Rgb([1., 2., 3.]))
+            .save(std::env::temp_dir().join(&name))
+            .unwrap();
+        let json = serde_json::json!({ "path": name }).to_string();
+
+        let map = Model::in_directory(&std::env::temp_dir(), || {
+            serde_json::from_str::<EnvironmentMap>(&json)
+        })
+        .unwrap();
+
+        assert_eq!(map.color(Vec3::j()), Color::new(1., 2., 3.));
+        assert!(serde_json::to_string(&map).unwrap().contains(&name));
+    }
+
+    #[test]
+    fn missing_environment_is_reported() {
+        let json = r#"{"path": "does/not/exist.hdr"}"#;
+
+        let err = serde_json::from_str::<EnvironmentMap>(json).err().unwrap();
+
+        assert!(err.to_string().contains("does/not/exist.hdr"));
+    }
+}
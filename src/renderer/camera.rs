@@ -1,37 +1,257 @@
+use serde::{Deserialize, Serialize};
+
 use crate::geometry::{Point3, Ray, Vec3};
+use crate::sampler::Sampler;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(try_from = "CameraData", into = "CameraData")]
+pub struct CameraDescription {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    /// Vertical field of view, in degrees
+    pub vertical_fov: f64,
+    /// Diameter of the lens; zero gives a pinhole camera with everything in focus
+    pub aperture: f64,
+    /// Distance to the plane in perfect focus, defaulting to the distance to `look_at`
+    pub focus_distance: Option<f64>,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            look_from: Point3::zeros(),
+            look_at: -Vec3::k(),
+            vup: Vec3::j(),
+            vertical_fov: 90.,
+            aperture: 0.,
+            focus_distance: None,
+        }
+    }
+}
+
+/// How a camera is written in scene files, checked for a view direction the camera can be
+/// lined up along before it's used
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+struct CameraData {
+    look_from: Point3,
+    look_at: Point3,
+    vup: Vec3,
+    vertical_fov: f64,
+    aperture: f64,
+    focus_distance: Option<f64>,
+}
+
+impl Default for CameraData {
+    fn default() -> Self {
+        CameraDescription::default().into()
+    }
+}
+
+impl TryFrom<CameraData> for CameraDescription {
+    type Error = String;
+
+    fn try_from(data: CameraData) -> Result<Self, Self::Error> {
+        let direction = data.look_at - data.look_from;
+        if direction.near_zero() {
+            return Err("a camera can't look at the point it's looking from".into());
+        }
+        if data.vup.near_zero()
+            || data
+                .vup
+                .unit_vector()
+                .cross(direction.unit_vector())
+                .near_zero()
+        {
+            return Err("a camera's vup can't point along the direction it looks in".into());
+        }
+        Ok(Self {
+            look_from: data.look_from,
+            look_at: data.look_at,
+            vup: data.vup,
+            vertical_fov: data.vertical_fov,
+            aperture: data.aperture,
+            focus_distance: data.focus_distance,
+        })
+    }
+}
+
+impl From<CameraDescription> for CameraData {
+    fn from(description: CameraDescription) -> Self {
+        Self {
+            look_from: description.look_from,
+            look_at: description.look_at,
+            vup: description.vup,
+            vertical_fov: description.vertical_fov,
+            aperture: description.aperture,
+            focus_distance: description.focus_distance,
+        }
+    }
+}
 
 pub struct Camera {
     pub origin: Point3,
     pub lower_left_corner: Point3,
     pub horizontal: Vec3,
     pub vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    lens_radius: f64,
 }
 
 impl Camera {
-    pub fn new(image_width: u32, image_heigth: u32) -> Self {
+    pub fn new(description: &CameraDescription, image_width: u32, image_heigth: u32) -> Self {
         let aspect_ratio = image_width as f64 / image_heigth as f64;
-        let viewport_heigth = 2.;
+        let theta = description.vertical_fov.to_radians();
+        let viewport_heigth = 2. * (theta / 2.).tan();
         let viewport_width = viewport_heigth * aspect_ratio;
-        let focal_length = 1.;
 
-        let origin = Vec3::zeros();
-        let horizontal = viewport_width * Vec3::i();
-        let vertical = viewport_heigth * Vec3::j();
-        let lower_left_corner =
-            origin - horizontal / 2. - vertical / 2. - (focal_length * Vec3::k());
+        let w = (description.look_from - description.look_at).unit_vector();
+        let u = description.vup.cross(w).unit_vector();
+        let v = w.cross(u);
+
+        let focus_distance = description
+            .focus_distance
+            .unwrap_or_else(|| (description.look_from - description.look_at).length());
+
+        let origin = description.look_from;
+        let horizontal = focus_distance * viewport_width * u;
+        let vertical = focus_distance * viewport_heigth * v;
+        let lower_left_corner = origin - horizontal / 2. - vertical / 2. - focus_distance * w;
 
         Self {
             origin,
             lower_left_corner,
             horizontal,
             vertical,
+            u,
+            v,
+            lens_radius: description.aperture / 2.,
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
+    /// Distance from the camera to `point` along the direction it looks in
+    pub fn depth(&self, point: Point3) -> f64 {
+        (point - self.origin).dot(self.v.cross(self.u))
+    }
+
+    pub fn get_ray<S: Sampler + ?Sized>(&self, s: f64, t: f64, sampler: &mut S) -> Ray {
+        let offset = if self.lens_radius > 0. {
+            let lens_point = self.lens_radius * Vec3::random_in_unit_disk(sampler);
+            lens_point.x * self.u + lens_point.y * self.v
+        } else {
+            Vec3::zeros()
+        };
+
         Ray::new(
-            self.origin,
-            self.lower_left_corner + u * self.horizontal + v * self.vertical,
+            self.origin + offset,
+            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::assert_within;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64Mcg;
+
+    #[test]
+    fn default_camera_looks_down_negative_z() {
+        let camera = Camera::new(&CameraDescription::default(), 16, 9);
+        let mut rng = Pcg64Mcg::seed_from_u64(0);
+
+        assert_eq!(camera.origin, Vec3::zeros());
+        assert_within(
+            camera.get_ray(0.5, 0.5, &mut rng).direction,
+            -Vec3::k(),
+            1e-12,
+        );
+        assert_within(camera.vertical, 2. * Vec3::j(), 1e-12);
+        assert_within(camera.horizontal, 2. * 16. / 9. * Vec3::i(), 1e-12);
+    }
+
+    #[test]
+    fn camera_looks_at_target() {
+        let description = CameraDescription {
+            look_from: Vec3::new(3., 3., 2.),
+            look_at: Vec3::new(0., 0., -1.),
+            vup: Vec3::j(),
+            vertical_fov: 20.,
+            ..Default::default()
+        };
+        let camera = Camera::new(&description, 400, 225);
+
+        let ray = camera.get_ray(0.5, 0.5, &mut Pcg64Mcg::seed_from_u64(0));
+
+        assert_eq!(ray.origin, description.look_from);
+        assert_within(
+            ray.direction,
+            (description.look_at - description.look_from).unit_vector(),
+            1e-12,
+        );
+        assert!(camera.vertical.dot(Vec3::j()) > 0.);
+    }
+
+    #[test]
+    fn field_of_view_sets_viewport_height() {
+        let description = CameraDescription {
+            vertical_fov: 60.,
+            ..Default::default()
+        };
+        let camera = Camera::new(&description, 100, 100);
+
+        let expected = 2. * 30f64.to_radians().tan();
+        assert!((camera.vertical.length() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lens_rays_converge_on_the_focus_plane() {
+        let description = CameraDescription {
+            aperture: 0.5,
+            focus_distance: Some(4.),
+            ..Default::default()
+        };
+        let camera = Camera::new(&description, 100, 100);
+        let mut rng = Pcg64Mcg::seed_from_u64(7);
+        let focus_point = Vec3::new(0.3, -0.2, -4.);
+        let (s, t) = (0.5 + 0.3 / 8., 0.5 - 0.2 / 8.);
+
+        for _ in 0..16 {
+            let ray = camera.get_ray(s, t, &mut rng);
+            assert!(ray.origin.length() <= 0.25);
+            let distance = (focus_point - ray.origin).length();
+            assert_within(ray.at(distance), focus_point, 1e-12);
+        }
+    }
+
+    #[test]
+    fn cameras_without_a_view_direction_are_rejected() {
+        let point = r#"{ "x": 1.0, "y": 2.0, "z": 3.0 }"#;
+        let up_to_it = r#"{ "x": 0.0, "y": 1.0, "z": 0.0 }"#;
+        for (json, message) in [
+            (
+                format!(r#"{{ "look_from": {0}, "look_at": {0} }}"#, point),
+                "look at the point it's looking from",
+            ),
+            (
+                format!(r#"{{ "look_at": {} }}"#, up_to_it),
+                "vup can't point along",
+            ),
+            (
+                r#"{ "vup": { "x": 0.0, "y": 0.0, "z": 0.0 } }"#.to_owned(),
+                "vup can't point along",
+            ),
+        ] {
+            let err = serde_json::from_str::<CameraDescription>(&json)
+                .err()
+                .unwrap();
+
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+        assert!(serde_json::from_str::<CameraDescription>("{}").is_ok());
+    }
+}
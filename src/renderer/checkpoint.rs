@@ -0,0 +1,323 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ArgEnum;
+
+use super::accumulator::PixelAccumulator;
+use super::film::FilmPixel;
+use super::filter::{Filter, FilterKind};
+use crate::geometry::Hittable;
+use crate::sampler::SamplerKind;
+use crate::scene::SceneDescription;
+
+const MAGIC: &[u8; 8] = b"RRTCKPT4";
+
+/// Everything needed to carry on with an interrupted render: the samples taken so far and the
+/// settings they were taken with. Every sample's generator is seeded from the render seed and
+/// the sample's index, so the seed stands in for the generators' state.
+pub struct Checkpoint {
+    pub(super) scene_hash: u64,
+    pub(super) seed: u64,
+    pub(super) image_width: u32,
+    pub(super) image_height: u32,
+    pub(super) max_depth: usize,
+    pub(super) roulette_depth: usize,
+    pub(super) sampler: SamplerKind,
+    pub(super) filter: Filter,
+    pub(super) pixels: Vec<PixelAccumulator>,
+    pub(super) film: Vec<FilmPixel>,
+}
+
+impl Checkpoint {
+    /// Read a checkpoint, making sure it was written while rendering `scene`
+    pub fn load(path: &Path, scene: &SceneDescription) -> Result<Self, CheckpointError> {
+        let file = File::open(path).map_err(|err| CheckpointError::Io(path.to_owned(), err))?;
+        let checkpoint = Self::read(&mut BufReader::new(file)).map_err(|err| match err.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                CheckpointError::Invalid(path.to_owned())
+            }
+            _ => CheckpointError::Io(path.to_owned(), err),
+        })?;
+        if checkpoint.scene_hash != scene_hash(scene) {
+            return Err(CheckpointError::SceneChanged(path.to_owned()));
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        // Write beside the old checkpoint and swap it in, so being stopped partway through
+        // doesn't lose both
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let mut writer = BufWriter::new(File::create(&partial)?);
+        self.write(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(partial, path)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.scene_hash.to_le_bytes())?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&self.image_width.to_le_bytes())?;
+        writer.write_all(&self.image_height.to_le_bytes())?;
+        writer.write_all(&(self.max_depth as u64).to_le_bytes())?;
+        writer.write_all(&(self.roulette_depth as u64).to_le_bytes())?;
+        writer.write_all(&(self.sampler as u32).to_le_bytes())?;
+        writer.write_all(&(self.filter.kind as u32).to_le_bytes())?;
+        writer.write_all(&self.filter.radius().to_le_bytes())?;
+        self.pixels
+            .iter()
+            .try_for_each(|pixel| pixel.write(writer))?;
+        self.film.iter().try_for_each(|pixel| pixel.write(writer))
+    }
+
+    fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        let mut u64_bytes = [0; 8];
+        let mut u32_bytes = [0; 4];
+        let mut next_u64 = |reader: &mut R| {
+            reader.read_exact(&mut u64_bytes)?;
+            io::Result::Ok(u64::from_le_bytes(u64_bytes))
+        };
+        let scene_hash = next_u64(reader)?;
+        let seed = next_u64(reader)?;
+        reader.read_exact(&mut u32_bytes)?;
+        let image_width = u32::from_le_bytes(u32_bytes);
+        reader.read_exact(&mut u32_bytes)?;
+        let image_height = u32::from_le_bytes(u32_bytes);
+        let max_depth = next_u64(reader)? as usize;
+        let roulette_depth = next_u64(reader)? as usize;
+        reader.read_exact(&mut u32_bytes)?;
+        let sampler = kind_from_tag(u32::from_le_bytes(u32_bytes), |kind: SamplerKind| {
+            kind as u32
+        })?;
+        reader.read_exact(&mut u32_bytes)?;
+        let kind = kind_from_tag(u32::from_le_bytes(u32_bytes), |kind: FilterKind| {
+            kind as u32
+        })?;
+        let radius = f64::from_bits(next_u64(reader)?);
+        let num_pixels = image_width as usize * image_height as usize;
+        let pixels = (0..num_pixels)
+            .map(|_| PixelAccumulator::read(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let film = (0..num_pixels)
+            .map(|_| FilmPixel::read(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        if reader.read(&mut [0])? != 0 {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        Ok(Self {
+            scene_hash,
+            seed,
+            image_width,
+            image_height,
+            max_depth,
+            roulette_depth,
+            sampler,
+            filter: Filter {
+                kind,
+                radius: Some(radius),
+            },
+            pixels,
+            film,
+        })
+    }
+}
+
+/// The kind written as `tag`, which is its explicitly numbered discriminant rather than where it
+/// comes in the list of kinds, so adding kinds doesn't change what old checkpoints mean
+fn kind_from_tag<T: ArgEnum + Copy>(tag: u32, as_tag: fn(T) -> u32) -> io::Result<T> {
+    T::value_variants()
+        .iter()
+        .copied()
+        .find(|&kind| as_tag(kind) == tag)
+        .ok_or_else(|| io::ErrorKind::InvalidData.into())
+}
+
+/// FNV-1a over the parts of the scene that change what gets rendered, along with the contents of
+/// the model and image files it read, which unlike the standard library's hasher stays the same between
+/// builds
+pub(super) fn scene_hash(scene: &SceneDescription) -> u64 {
+    let mut contents = serde_json::to_vec(&(
+        &scene.camera,
+        &scene.background,
+        &scene.lights,
+        &scene.objects,
+        &scene.hittable,
+    ))
+    .unwrap();
+    let files = scene.hittable.source_files();
+    for file in files.into_iter().chain(scene.background.source_files()) {
+        // A file that's gone since the scene was loaded still leaves a different hash
+        contents.extend(fs::read(file).unwrap_or_default());
+    }
+    fnv1a(&contents)
+}
+
+/// The 64-bit FNV-1a hash, which is the same on every platform and in every build
+pub(super) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf),
+    SceneChanged(PathBuf),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "Could not read checkpoint {:?}: {}", path, err),
+            Self::Invalid(path) => write!(f, "{:?} is not a complete checkpoint", path),
+            Self::SceneChanged(path) => {
+                write!(f, "Checkpoint {:?} was made from a different scene", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Color;
+
+    fn checkpoint() -> Checkpoint {
+        let mut pixels = vec![PixelAccumulator::default(); 6];
+        pixels[4].add(Color::new(0.25, 0.5, 1.));
+        let mut film = vec![FilmPixel::default(); 6];
+        film[4].add(Color::new(0.25, 0.5, 1.), 0.75);
+        Checkpoint {
+            scene_hash: scene_hash(&SceneDescription::default()),
+            seed: 42,
+            image_width: 3,
+            image_height: 2,
+            max_depth: 7,
+            roulette_depth: 3,
+            sampler: SamplerKind::Sobol,
+            filter: Filter {
+                kind: FilterKind::Mitchell,
+                radius: Some(1.5),
+            },
+            pixels,
+            film,
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rust_ray_tracer_{}.checkpoint", name))
+    }
+
+    #[test]
+    fn checkpoints_survive_saving_and_loading() {
+        let path = temp_path("round_trip");
+        checkpoint().save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path, &SceneDescription::default()).unwrap();
+
+        assert_eq!(loaded.seed, 42);
+        assert_eq!((loaded.image_width, loaded.image_height), (3, 2));
+        assert_eq!((loaded.max_depth, loaded.roulette_depth), (7, 3));
+        assert_eq!(loaded.sampler, SamplerKind::Sobol);
+        assert_eq!(loaded.filter, checkpoint().filter);
+        assert!(loaded.pixels == checkpoint().pixels);
+        assert!(loaded.film == checkpoint().film);
+    }
+
+    #[test]
+    fn changed_scenes_are_rejected() {
+        let path = temp_path("changed_scene");
+        checkpoint().save(&path).unwrap();
+        let mut scene = SceneDescription::default();
+        scene.camera.vertical_fov += 1.;
+
+        let err = Checkpoint::load(&path, &scene).err().unwrap();
+
+        assert!(matches!(err, CheckpointError::SceneChanged(_)));
+    }
+
+    #[test]
+    fn changed_model_files_are_rejected() {
+        let path = temp_path("changed_model");
+        let model = std::env::temp_dir().join(format!(
+            "rust_ray_tracer_checkpoint_{}.obj",
+            std::process::id()
+        ));
+        fs::write(&model, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let json = serde_json::json!({
+            "hittable": { "list": [{ "Model": { "path": model } }] }
+        });
+        let scene: SceneDescription = serde_json::from_value(json).unwrap();
+        Checkpoint {
+            scene_hash: scene_hash(&scene),
+            ..checkpoint()
+        }
+        .save(&path)
+        .unwrap();
+        assert!(Checkpoint::load(&path, &scene).is_ok());
+
+        fs::write(&model, "v 0 0 0\nv 2 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let err = Checkpoint::load(&path, &scene).err().unwrap();
+
+        assert!(matches!(err, CheckpointError::SceneChanged(_)));
+    }
+
+    #[test]
+    fn changed_environment_maps_are_rejected() {
+        let path = temp_path("changed_environment");
+        let map = std::env::temp_dir().join(format!(
+            "rust_ray_tracer_checkpoint_{}.exr",
+            std::process::id()
+        ));
+        let save_map = |value| {
+            image::Rgb32FImage::from_pixel(2, 1, image::Rgb([value; 3]))
+                .save(&map)
+                .unwrap()
+        };
+        save_map(1.);
+        let json = serde_json::json!({
+            "background": { "Environment": { "path": map } },
+            "hittable": { "list": [] }
+        });
+        let scene: SceneDescription = serde_json::from_value(json).unwrap();
+        Checkpoint {
+            scene_hash: scene_hash(&scene),
+            ..checkpoint()
+        }
+        .save(&path)
+        .unwrap();
+        assert!(Checkpoint::load(&path, &scene).is_ok());
+
+        save_map(2.);
+        let err = Checkpoint::load(&path, &scene).err().unwrap();
+
+        assert!(matches!(err, CheckpointError::SceneChanged(_)));
+    }
+
+    #[test]
+    fn truncated_checkpoints_are_rejected() {
+        let path = temp_path("truncated");
+        let mut bytes = Vec::new();
+        checkpoint().write(&mut bytes).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+
+        let err = Checkpoint::load(&path, &SceneDescription::default())
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, CheckpointError::Invalid(_)));
+    }
+}
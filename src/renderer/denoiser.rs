@@ -0,0 +1,266 @@
+use image::Rgb;
+
+use super::{Aovs, ImageBuffer};
+use crate::geometry::{Color, Vec3};
+
+/// Taps of the B3 spline the à-trous filter is built from
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// An edge-avoiding à-trous wavelet filter, after Dammertz et al. Each iteration blurs with
+/// taps twice as far apart as the last, and the albedo, normal and depth buffers keep it from
+/// blurring across the edges of objects. Noise in the lighting is smoothed out while textures
+/// stay sharp, since the lighting is filtered with the albedo divided out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Denoiser {
+    /// How many times to filter, each reaching twice as far
+    pub iterations: u32,
+    /// Difference in lighting, relative to the brighter of the two pixels, that brings a
+    /// neighbour's weight down to `1/e` on the first iteration. Later ones are twice as strict.
+    pub color_sigma: f64,
+    /// How sharply the weight falls off as normals turn apart
+    pub normal_power: f64,
+    /// Relative change in depth per pixel apart that counts as a different surface
+    pub depth_sigma: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 1.,
+            normal_power: 64.,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+impl Denoiser {
+    /// Denoise linear radiance rendered with the given buffers
+    pub fn denoise(&self, image: &ImageBuffer, aovs: &Aovs) -> ImageBuffer {
+        let (width, height) = image.dimensions();
+        let pixel = |buffer: &ImageBuffer, x: u32, y: u32| {
+            let Rgb([r, g, b]) = *buffer.get_pixel(x, y);
+            Vec3::new(r as f64, g as f64, b as f64)
+        };
+        let guides = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| Guide {
+                albedo: pixel(&aovs.albedo, x, y),
+                normal: pixel(&aovs.normal, x, y),
+                depth: pixel(&aovs.depth, x, y).x,
+            })
+            .collect::<Vec<_>>();
+        let mut lighting = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .zip(&guides)
+            .map(|((x, y), guide)| demodulate(pixel(image, x, y), guide.albedo))
+            .collect::<Vec<_>>();
+
+        for iteration in 0..self.iterations {
+            lighting = self.filter(&lighting, &guides, width, height, iteration);
+        }
+
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let index = y as usize * width as usize + x as usize;
+            remodulate(lighting[index], guides[index].albedo).into()
+        })
+    }
+
+    fn filter(
+        &self,
+        lighting: &[Color],
+        guides: &[Guide],
+        width: u32,
+        height: u32,
+        iteration: u32,
+    ) -> Vec<Color> {
+        let step = 1i64 << iteration;
+        let color_sigma = self.color_sigma / 2f64.powi(iteration as i32);
+        let index = |x: i64, y: i64| y as usize * width as usize + x as usize;
+        (0..height as i64)
+            .flat_map(|y| (0..width as i64).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let center = lighting[index(x, y)];
+                let guide = &guides[index(x, y)];
+                let mut sum = Color::zeros();
+                let mut weight_sum = 0.;
+                for (j, kernel_y) in KERNEL.iter().enumerate() {
+                    for (i, kernel_x) in KERNEL.iter().enumerate() {
+                        let (offset_x, offset_y) = ((i as i64 - 2) * step, (j as i64 - 2) * step);
+                        let (other_x, other_y) = (x + offset_x, y + offset_y);
+                        if other_x < 0
+                            || other_y < 0
+                            || other_x >= width as i64
+                            || other_y >= height as i64
+                        {
+                            continue;
+                        }
+                        let other = lighting[index(other_x, other_y)];
+                        let other_guide = &guides[index(other_x, other_y)];
+                        let distance = ((offset_x.pow(2) + offset_y.pow(2)) as f64).sqrt();
+
+                        let brightness = center.max(other).max_component().max(1e-3);
+                        let color_weight = (-(other - center).length_squared()
+                            / (color_sigma * brightness).powi(2))
+                        .exp();
+                        let weight = kernel_x
+                            * kernel_y
+                            * color_weight
+                            * self.normal_weight(guide.normal, other_guide.normal)
+                            * self.depth_weight(guide.depth, other_guide.depth, distance);
+                        sum += weight * other;
+                        weight_sum += weight;
+                    }
+                }
+                // The center always weighs something, so this never divides by zero
+                sum / weight_sum
+            })
+            .collect()
+    }
+
+    /// Pixels showing the background have no normal, and only blend with each other
+    fn normal_weight(&self, normal: Vec3, other: Vec3) -> f64 {
+        match (normal.near_zero(), other.near_zero()) {
+            (true, true) => 1.,
+            (false, false) => {
+                let cosine = normal.dot(other) / (normal.length() * other.length());
+                cosine.max(0.).powf(self.normal_power)
+            }
+            _ => 0.,
+        }
+    }
+
+    fn depth_weight(&self, depth: f64, other: f64, distance: f64) -> f64 {
+        let scale = self.depth_sigma * depth.abs().max(1e-3) * distance.max(1.);
+        (-(depth - other).abs() / scale).exp()
+    }
+}
+
+/// What the denoiser steers by at one pixel
+struct Guide {
+    albedo: Color,
+    normal: Vec3,
+    depth: f64,
+}
+
+/// Divide the albedo out of the color, leaving the light falling on the surface. Black albedos
+/// are left alone, as there's nothing to divide out.
+fn demodulate(color: Color, albedo: Color) -> Color {
+    let divide = |value: f64, albedo: f64| {
+        if albedo > 1e-3 {
+            value / albedo
+        } else {
+            value
+        }
+    };
+    Color::new(
+        divide(color.x, albedo.x),
+        divide(color.y, albedo.y),
+        divide(color.z, albedo.z),
+    )
+}
+
+fn remodulate(lighting: Color, albedo: Color) -> Color {
+    let multiply = |value: f64, albedo: f64| {
+        if albedo > 1e-3 {
+            value * albedo
+        } else {
+            value
+        }
+    };
+    Color::new(
+        multiply(lighting.x, albedo.x),
+        multiply(lighting.y, albedo.y),
+        multiply(lighting.z, albedo.z),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sampler::mix;
+
+    const SIZE: u32 = 32;
+
+    fn buffer(value: impl Fn(u32, u32) -> Color) -> ImageBuffer {
+        ImageBuffer::from_fn(SIZE, SIZE, |x, y| value(x, y).into())
+    }
+
+    /// One flat surface facing the camera, or two meeting at a right angle down the middle
+    fn aovs(albedo: impl Fn(u32, u32) -> Color, split: bool) -> Aovs {
+        let normal = move |x: u32| {
+            if split && x >= SIZE / 2 {
+                Vec3::i()
+            } else {
+                Vec3::k()
+            }
+        };
+        Aovs {
+            albedo: buffer(albedo),
+            normal: buffer(|x, _| normal(x)),
+            depth: buffer(|_, _| Color::ones()),
+            object_id: buffer(|_, _| Color::zeros()),
+            material_id: buffer(|_, _| Color::zeros()),
+        }
+    }
+
+    /// Noise in `[-0.5, 0.5)` that only depends on the pixel
+    fn noise(x: u32, y: u32) -> f64 {
+        (mix((y * SIZE + x) as u64) >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    }
+
+    fn mean_squared_error(image: &ImageBuffer, expected: impl Fn(u32, u32) -> f32) -> f32 {
+        image
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| (pixel.0[0] - expected(x, y)).powi(2))
+            .sum::<f32>()
+            / (SIZE * SIZE) as f32
+    }
+
+    #[test]
+    fn noise_on_flat_surfaces_is_smoothed_out() {
+        let noisy = buffer(|x, y| (1. + 0.5 * noise(x, y)) * Color::ones());
+
+        let denoised = Denoiser::default().denoise(&noisy, &aovs(|_, _| Color::ones(), false));
+
+        let error = mean_squared_error(&denoised, |_, _| 1.);
+        assert!(
+            error < mean_squared_error(&noisy, |_, _| 1.) / 10.,
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn edges_between_surfaces_stay_sharp() {
+        let expected = |x: u32, _| if x >= SIZE / 2 { 1. } else { 0.2 };
+        let noisy = buffer(|x, y| (expected(x, y) as f64 + 0.1 * noise(x, y)) * Color::ones());
+
+        let denoised = Denoiser::default().denoise(&noisy, &aovs(|_, _| Color::ones(), true));
+
+        for x in [SIZE / 2 - 1, SIZE / 2] {
+            let pixel = denoised.get_pixel(x, SIZE / 2).0[0];
+            assert!((pixel - expected(x, 0)).abs() < 0.1, "{} at {}", pixel, x);
+        }
+    }
+
+    #[test]
+    fn textures_survive_denoising() {
+        let checker = |x: u32, y: u32| {
+            if (x / 2 + y / 2).is_multiple_of(2) {
+                Color::new(0.9, 0.5, 0.1)
+            } else {
+                Color::new(0.1, 0.2, 0.8)
+            }
+        };
+        let image = buffer(|x, y| 2. * checker(x, y));
+
+        let denoised = Denoiser::default().denoise(&image, &aovs(checker, false));
+
+        for (expected, pixel) in image.pixels().zip(denoised.pixels()) {
+            for (expected, value) in expected.0.iter().zip(pixel.0) {
+                assert!((expected - value).abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::io::{self, Read, Write};
+use std::ops::AddAssign;
+
+use super::filter::Filter;
+use super::tile::Tile;
+use crate::geometry::Color;
+
+/// Filter weighted totals of the samples that landed on one pixel
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FilmPixel {
+    weighted_sum: Color,
+    weight_sum: f64,
+}
+
+impl FilmPixel {
+    pub fn add(&mut self, color: Color, weight: f64) {
+        self.weighted_sum += weight * color;
+        self.weight_sum += weight;
+    }
+
+    /// The weighted mean, or black before anything has landed
+    pub fn value(&self) -> Color {
+        if self.weight_sum == 0. {
+            Color::zeros()
+        } else {
+            self.weighted_sum / self.weight_sum
+        }
+    }
+
+    /// Write the totals exactly, as little-endian numbers
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for value in [
+            self.weighted_sum.x,
+            self.weighted_sum.y,
+            self.weighted_sum.z,
+            self.weight_sum,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut next = || -> io::Result<f64> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(f64::from_le_bytes(bytes))
+        };
+        Ok(Self {
+            weighted_sum: Color::new(next()?, next()?, next()?),
+            weight_sum: next()?,
+        })
+    }
+}
+
+impl AddAssign for FilmPixel {
+    fn add_assign(&mut self, other: Self) {
+        self.weighted_sum += other.weighted_sum;
+        self.weight_sum += other.weight_sum;
+    }
+}
+
+/// The part of the film a tile's samples can land on: the tile itself, carrying on from the
+/// totals so far, and a margin around it that only collects what this tile adds.
+///
+/// Taking over the tile's own totals rather than adding to them afterwards means pixels that
+/// only see their own samples sum them in the same order however the render is split into passes.
+pub struct FilmTile {
+    tile: Tile,
+    bounds: Tile,
+    pixels: Vec<FilmPixel>,
+}
+
+impl FilmTile {
+    pub fn new(tile: Tile, filter: &Filter, film: &[FilmPixel], image_width: u32) -> Self {
+        let image_height = (film.len() / image_width as usize) as u32;
+        let bounds = tile.expand(filter.reach(), image_width, image_height);
+        let pixels = bounds
+            .pixels()
+            .map(|(x, y)| {
+                if tile.contains(x, y) {
+                    film[y as usize * image_width as usize + x as usize]
+                } else {
+                    FilmPixel::default()
+                }
+            })
+            .collect();
+        Self {
+            tile,
+            bounds,
+            pixels,
+        }
+    }
+
+    /// Spread a sample taken `offset` pixels from the center of pixel `x`, `y` over every
+    /// pixel the filter reaches
+    pub fn splat(&mut self, filter: &Filter, x: u32, y: u32, offset: (f64, f64), color: Color) {
+        let reach = filter.reach() as i64;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let (target_x, target_y) = (x as i64 + dx, y as i64 + dy);
+                if target_x < 0
+                    || target_y < 0
+                    || !self.bounds.contains(target_x as u32, target_y as u32)
+                {
+                    continue;
+                }
+                let weight = filter.weight(dx as f64 - offset.0, dy as f64 - offset.1);
+                if weight != 0. {
+                    let index = (target_y as u32 - self.bounds.y) as usize
+                        * self.bounds.width as usize
+                        + (target_x as u32 - self.bounds.x) as usize;
+                    self.pixels[index].add(color, weight);
+                }
+            }
+        }
+    }
+
+    /// Replace the totals of the tile's own pixels on the film
+    pub fn write_tile(&self, film: &mut [FilmPixel], image_width: u32) {
+        for ((x, y), pixel) in self.bounds.pixels().zip(&self.pixels) {
+            if self.tile.contains(x, y) {
+                film[y as usize * image_width as usize + x as usize] = *pixel;
+            }
+        }
+    }
+
+    /// Add what landed in the margin onto the film. Runs after every tile has been written, so
+    /// nothing gets overwritten.
+    pub fn add_margin(&self, film: &mut [FilmPixel], image_width: u32) {
+        for ((x, y), pixel) in self.bounds.pixels().zip(&self.pixels) {
+            if !self.tile.contains(x, y) {
+                film[y as usize * image_width as usize + x as usize] += *pixel;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::renderer::filter::FilterKind;
+    use pretty_assertions::assert_eq;
+
+    fn tent() -> Filter {
+        Filter {
+            kind: FilterKind::Tent,
+            radius: Some(1.),
+        }
+    }
+
+    #[test]
+    fn splats_are_weighted_into_neighbours() {
+        let tile = Tile {
+            x: 1,
+            y: 1,
+            width: 1,
+            height: 1,
+        };
+        let mut film = vec![FilmPixel::default(); 9];
+        let mut film_tile = FilmTile::new(tile, &tent(), &film, 3);
+
+        film_tile.splat(&tent(), 1, 1, (0.5, 0.), Color::ones());
+        film_tile.write_tile(&mut film, 3);
+        film_tile.add_margin(&mut film, 3);
+
+        let weights = film
+            .iter()
+            .map(|pixel| pixel.weight_sum)
+            .collect::<Vec<_>>();
+        assert_eq!(weights, vec![0., 0., 0., 0., 0.5, 0.5, 0., 0., 0.]);
+        assert_eq!(film[5].value(), Color::ones());
+    }
+
+    #[test]
+    fn tiles_carry_on_from_their_own_pixels() {
+        let tile = Tile {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        let mut film = vec![FilmPixel::default(); 2];
+        film[0].add(Color::zeros(), 1.);
+        film[1].add(Color::ones(), 1.);
+        let mut film_tile = FilmTile::new(tile, &tent(), &film, 2);
+
+        film_tile.splat(&tent(), 0, 0, (0., 0.), Color::ones());
+        film_tile.write_tile(&mut film, 2);
+        film_tile.add_margin(&mut film, 2);
+
+        assert_eq!(film[0].value(), 0.5 * Color::ones());
+        assert_eq!(film[1].value(), Color::ones());
+    }
+
+    #[test]
+    fn samples_past_the_image_edge_are_dropped() {
+        let tile = Tile {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        let mut film_tile = FilmTile::new(tile, &tent(), &[FilmPixel::default()], 1);
+
+        film_tile.splat(&tent(), 0, 0, (-0.5, 0.5), Color::ones());
+
+        assert_eq!(film_tile.pixels[0].weight_sum, 0.25);
+    }
+}
@@ -0,0 +1,216 @@
+use std::f64::consts::PI;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Furthest a filter may reach, in pixels. Every sample is splatted into every pixel within its
+/// reach, so much further than this and a render would never finish.
+pub const MAX_RADIUS: f64 = 16.;
+
+/// The shape of the weight a sample carries into the pixels around it. Checkpoints store each
+/// kind as its discriminant, so those must never change.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, clap::ArgEnum)]
+pub enum FilterKind {
+    /// Equal weight everywhere within the radius; with a radius of half a pixel every sample
+    /// lands only in its own pixel
+    Box = 0,
+    /// Falls off linearly to zero at the radius
+    Tent = 1,
+    /// A Gaussian with a standard deviation of a third of the radius, shifted to reach zero there
+    Gaussian = 2,
+    /// Mitchell–Netravali with `B = C = 1/3`, which sharpens edges with small negative lobes
+    Mitchell = 3,
+    /// A windowed sinc with as many lobes as the radius has pixels
+    Lanczos = 4,
+}
+
+impl FilterKind {
+    pub fn default_radius(self) -> f64 {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.,
+            Self::Lanczos => 3.,
+        }
+    }
+}
+
+/// How the samples around a pixel are weighted together into its value
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filter {
+    pub kind: FilterKind,
+    /// Furthest a sample reaches, in pixels, or `None` for the kind's default
+    #[serde(deserialize_with = "valid_radius")]
+    pub radius: Option<f64>,
+}
+
+fn valid_radius<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(radius) if !(radius > 0. && radius <= MAX_RADIUS) => Err(D::Error::custom(format!(
+            "{} is not a filter radius between 0 and {} pixels",
+            radius, MAX_RADIUS
+        ))),
+        radius => Ok(radius),
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            kind: FilterKind::Box,
+            radius: None,
+        }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        self.radius.unwrap_or_else(|| self.kind.default_radius())
+    }
+
+    /// How many pixels away from its own a sample, jittered at most half a pixel from the
+    /// center, can land
+    pub fn reach(&self) -> u32 {
+        (self.radius() + 0.5).floor() as u32
+    }
+
+    /// Weight of a sample `dx` by `dy` pixels away from a pixel's center
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, x: f64) -> f64 {
+        let radius = self.radius();
+        let x = x.abs();
+        if x > radius {
+            return 0.;
+        }
+        match self.kind {
+            FilterKind::Box => 1.,
+            FilterKind::Tent => 1. - x / radius,
+            FilterKind::Gaussian => {
+                let gaussian = |x: f64| (-x * x / (2. * (radius / 3.).powi(2))).exp();
+                (gaussian(x) - gaussian(radius)).max(0.)
+            }
+            FilterKind::Mitchell => mitchell(2. * x / radius),
+            FilterKind::Lanczos => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+/// The Mitchell–Netravali cubic over `[0, 2]`
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1. / 3.;
+    const C: f64 = 1. / 3.;
+    let value = if x < 1. {
+        (12. - 9. * B - 6. * C) * x.powi(3) + (-18. + 12. * B + 6. * C) * x.powi(2) + (6. - 2. * B)
+    } else {
+        (-B - 6. * C) * x.powi(3)
+            + (6. * B + 30. * C) * x.powi(2)
+            + (-12. * B - 48. * C) * x
+            + (8. * B + 24. * C)
+    };
+    value / 6.
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KINDS: [FilterKind; 5] = [
+        FilterKind::Box,
+        FilterKind::Tent,
+        FilterKind::Gaussian,
+        FilterKind::Mitchell,
+        FilterKind::Lanczos,
+    ];
+
+    fn filter(kind: FilterKind) -> Filter {
+        Filter { kind, radius: None }
+    }
+
+    #[test]
+    fn weights_peak_in_the_center_and_vanish_past_the_radius() {
+        for kind in KINDS {
+            let filter = filter(kind);
+            let radius = filter.radius();
+            assert!(filter.weight(0., 0.) > 0., "{:?}", kind);
+            assert!(
+                filter.weight(0.3, 0.) <= filter.weight(0., 0.),
+                "{:?}",
+                kind
+            );
+            assert_eq!(filter.weight(radius + 0.01, 0.), 0., "{:?}", kind);
+            assert_eq!(filter.weight(0., -radius - 0.01), 0., "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn filters_meet_zero_smoothly_at_their_radius() {
+        for kind in [FilterKind::Tent, FilterKind::Gaussian, FilterKind::Mitchell] {
+            let filter = filter(kind);
+            assert!(
+                filter.weight(filter.radius() - 1e-9, 0.).abs() < 1e-6,
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn sharpening_filters_have_negative_lobes() {
+        assert!(filter(FilterKind::Mitchell).weight(1.5, 0.) < 0.);
+        assert!(filter(FilterKind::Lanczos).weight(1.5, 0.) < 0.);
+    }
+
+    #[test]
+    fn default_box_stays_within_its_pixel() {
+        let filter = Filter::default();
+
+        assert_eq!(filter.weight(0.49, -0.49), 1.);
+        assert_eq!(filter.weight(0.51, 0.), 0.);
+    }
+
+    #[test]
+    fn radius_sets_how_far_samples_reach() {
+        let wide = Filter {
+            kind: FilterKind::Tent,
+            radius: Some(2.),
+        };
+
+        assert_eq!(filter(FilterKind::Box).reach(), 1);
+        assert_eq!(wide.reach(), 2);
+        assert!((wide.weight(1., 0.) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn radii_out_of_range_are_rejected() {
+        for radius in ["0.0", "-1.0", "1000.0"] {
+            let json = format!(r#"{{ "kind": "Tent", "radius": {} }}"#, radius);
+
+            let err = serde_json::from_str::<Filter>(&json).err().unwrap();
+
+            assert!(
+                err.to_string().contains("is not a filter radius"),
+                "{}",
+                err
+            );
+        }
+        let json = r#"{ "kind": "Tent", "radius": 2.0 }"#;
+        assert_eq!(
+            serde_json::from_str::<Filter>(json).unwrap().radius,
+            Some(2.)
+        );
+        assert_eq!(serde_json::from_str::<Filter>("{}").unwrap().radius, None);
+    }
+}
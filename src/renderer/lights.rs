@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{Color, HitEnum, HitRecord, Hittable, Point3, Ray, Sampleable, Vec3};
+use crate::material::Material;
+use crate::sampler::Sampler;
+
+/// A light with no size, which rays can only reach by aiming at it
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct PointLight {
+    pub position: Point3,
+    /// Radiance arriving from one unit away, falling off with the square of the distance
+    pub intensity: Color,
+}
+
+/// Everything shadow rays get aimed at for next event estimation. Light from emissive spheres,
+/// triangles, quads and disks is found both by aiming at them and by following scattered rays,
+/// with the two combined by multiple importance sampling.
+pub struct Lights {
+    area: Vec<HitEnum>,
+    points: Vec<PointLight>,
+}
+
+impl Lights {
+    pub fn new(world: &HitEnum, points: Vec<PointLight>) -> Self {
+        Self {
+            area: world.lights(),
+            points,
+        }
+    }
+
+    /// Light reaching the hit straight from the lights and leaving back along `ray`
+    pub fn direct<S: Sampler + ?Sized>(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        world: &HitEnum,
+        sampler: &mut S,
+    ) -> Color {
+        let material = &hit_record.material;
+        let mut color = Color::zeros();
+
+        for light in &self.points {
+            let to_light = light.position - hit_record.point;
+            let distance = to_light.length();
+            let direction = to_light / distance;
+            let reflectance = material.evaluate(ray, hit_record, direction);
+            let shadow_ray = Ray::new(hit_record.point, direction);
+            if reflectance != Color::zeros() && world.hit(&shadow_ray, 0.001..distance).is_none() {
+                color += reflectance * light.intensity / (distance * distance);
+            }
+        }
+
+        if !self.area.is_empty() {
+            let index = (sampler.get_1d() * self.area.len() as f64) as usize;
+            let light = &self.area[index.min(self.area.len() - 1)];
+            let direction = light.sample_direction(hit_record.point, sampler);
+            let light_pdf = self.area_pdf(hit_record.point, direction);
+            let reflectance = material.evaluate(ray, hit_record, direction);
+            if light_pdf > 0. && reflectance != Color::zeros() {
+                let shadow_ray = Ray::new(hit_record.point, direction);
+                if let Some(record) = world.hit(&shadow_ray, 0.001..f64::INFINITY) {
+                    let scatter_pdf = material.pdf(ray, hit_record, direction).unwrap_or(0.);
+                    color += reflectance
+                        * record.material.emitted(&record)
+                        * power_heuristic(light_pdf, scatter_pdf)
+                        / light_pdf;
+                }
+            }
+        }
+
+        color
+    }
+
+    /// How much of the light found by following a scattered ray from `origin` along
+    /// `direction` to count, given the density `scatter_pdf` it was picked with, since
+    /// `direct` may have already found some of it.
+    pub fn scatter_weight(&self, origin: Point3, direction: Vec3, scatter_pdf: f64) -> f64 {
+        if self.area.is_empty() {
+            return 1.;
+        }
+        power_heuristic(scatter_pdf, self.area_pdf(origin, direction))
+    }
+
+    /// Density of `direct` aiming along `direction`, with each area light equally likely
+    fn area_pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        self.area
+            .iter()
+            .map(|light| light.direction_pdf(origin, direction))
+            .sum::<f64>()
+            / self.area.len() as f64
+    }
+}
+
+/// Veach's power heuristic, weighting the sample taken with density `pdf` against one taken
+/// with `other_pdf`
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let pdf_squared = pdf * pdf;
+    let total = pdf_squared + other_pdf * other_pdf;
+    if total > 0. {
+        pdf_squared / total
+    } else {
+        0.
+    }
+}
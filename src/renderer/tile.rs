@@ -0,0 +1,117 @@
+/// A rectangular block of pixels that a single worker renders in one go.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Cover a `width` by `height` image with tiles of at most `size` pixels a side, in
+    /// scanline order.
+    pub fn split(width: u32, height: u32, size: u32) -> Vec<Self> {
+        (0..height)
+            .step_by(size as usize)
+            .flat_map(|y| {
+                (0..width).step_by(size as usize).map(move |x| Self {
+                    x,
+                    y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                })
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Grow the tile by `margin` pixels on every side, without going past the edges of a
+    /// `width` by `height` image.
+    pub fn expand(self, margin: u32, width: u32, height: u32) -> Self {
+        let x = self.x.saturating_sub(margin);
+        let y = self.y.saturating_sub(margin);
+        Self {
+            x,
+            y,
+            width: (self.x + self.width + margin).min(width) - x,
+            height: (self.y + self.height + margin).min(height) - y,
+        }
+    }
+
+    /// The image coordinates of every pixel in the tile, row by row.
+    pub fn pixels(self) -> impl Iterator<Item = (u32, u32)> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tiles_cover_every_pixel_once() {
+        let (width, height) = (37, 21);
+        let mut covered = vec![0; (width * height) as usize];
+
+        for tile in Tile::split(width, height, 8) {
+            assert_eq!(tile.pixels().count(), tile.len());
+            for (x, y) in tile.pixels() {
+                covered[(y * width + x) as usize] += 1;
+            }
+        }
+
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn edge_tiles_are_clipped() {
+        let tiles = Tile::split(10, 5, 8);
+        let expected = vec![
+            Tile {
+                x: 0,
+                y: 0,
+                width: 8,
+                height: 5,
+            },
+            Tile {
+                x: 8,
+                y: 0,
+                width: 2,
+                height: 5,
+            },
+        ];
+
+        assert_eq!(tiles, expected);
+    }
+
+    #[test]
+    fn expanded_tiles_stay_inside_the_image() {
+        let tile = Tile {
+            x: 8,
+            y: 0,
+            width: 8,
+            height: 5,
+        };
+
+        let expanded = tile.expand(2, 12, 5);
+
+        let expected = Tile {
+            x: 6,
+            y: 0,
+            width: 6,
+            height: 5,
+        };
+        assert_eq!(expanded, expected);
+        assert!(expanded.contains(11, 4));
+        assert!(!expanded.contains(12, 4));
+    }
+}
@@ -0,0 +1,208 @@
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+mod halton;
+mod sobol;
+mod stratified;
+
+pub use self::halton::HaltonSampler;
+pub use self::sobol::SobolSampler;
+pub use self::stratified::StratifiedSampler;
+
+/// The generator behind independent samples
+pub type SampleRng = Pcg64Mcg;
+
+/// Where the numbers behind one sample come from. Every call moves on to the next dimension of
+/// the sample, so samplers that spread their points out evenly do so along each dimension a
+/// path asks for, as long as the path asks in the same order every time.
+pub trait Sampler {
+    /// The next dimension, in `[0, 1)`
+    fn get_1d(&mut self) -> f64;
+
+    /// The next two dimensions, spread out together rather than each on its own
+    fn get_2d(&mut self) -> [f64; 2] {
+        [self.get_1d(), self.get_1d()]
+    }
+}
+
+/// Any random number generator takes independent samples
+impl<R: RngCore> Sampler for R {
+    fn get_1d(&mut self) -> f64 {
+        self.gen()
+    }
+}
+
+/// Checkpoints store each kind as its discriminant, so those must never change
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, clap::ArgEnum)]
+pub enum SamplerKind {
+    /// Every number drawn on its own
+    #[default]
+    Independent = 0,
+    /// One jittered point in each cell of a grid, shuffled differently in every dimension
+    Stratified = 1,
+    /// The Halton sequence, shifted by a different random amount for every pixel
+    Halton = 2,
+    /// Pairs of Sobol dimensions with hashed Owen scrambling
+    Sobol = 3,
+}
+
+impl SamplerKind {
+    /// Set up the sampler for sample `sample_index` of pixel `pixel_index`. It only depends on
+    /// its arguments, so the same sample gets the same numbers however the work is split up.
+    pub fn start(
+        self,
+        seed: u64,
+        pixel_index: u64,
+        sample_index: u64,
+        samples_per_pixel: usize,
+    ) -> SamplerEnum {
+        let pixel_seed = mix(seed ^ mix(pixel_index));
+        match self {
+            Self::Independent => {
+                SamplerEnum::Independent(SampleRng::seed_from_u64(mix(pixel_seed ^ sample_index)))
+            }
+            Self::Stratified => SamplerEnum::Stratified(StratifiedSampler::new(
+                pixel_seed,
+                sample_index,
+                samples_per_pixel as u64,
+            )),
+            Self::Halton => SamplerEnum::Halton(HaltonSampler::new(pixel_seed, sample_index)),
+            Self::Sobol => SamplerEnum::Sobol(SobolSampler::new(pixel_seed, sample_index)),
+        }
+    }
+}
+
+pub enum SamplerEnum {
+    Independent(SampleRng),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+}
+
+impl Sampler for SamplerEnum {
+    fn get_1d(&mut self) -> f64 {
+        match self {
+            SamplerEnum::Independent(x) => x.get_1d(),
+            SamplerEnum::Stratified(x) => x.get_1d(),
+            SamplerEnum::Halton(x) => x.get_1d(),
+            SamplerEnum::Sobol(x) => x.get_1d(),
+        }
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        match self {
+            SamplerEnum::Independent(x) => x.get_2d(),
+            SamplerEnum::Stratified(x) => x.get_2d(),
+            SamplerEnum::Halton(x) => x.get_2d(),
+            SamplerEnum::Sobol(x) => x.get_2d(),
+        }
+    }
+}
+
+/// The SplitMix64 finalizer, used to spread neighbouring indices over unrelated seeds.
+pub(crate) fn mix(value: u64) -> u64 {
+    let value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// A number in `[0, 1)` that looks random but depends only on its arguments
+fn hashed_unit(seed: u64, index: u64, dimension: u64) -> f64 {
+    let bits = mix(mix(seed ^ mix(dimension)) ^ index);
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KINDS: [SamplerKind; 4] = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified,
+        SamplerKind::Halton,
+        SamplerKind::Sobol,
+    ];
+
+    /// The first dimension of every sample of one pixel
+    fn first_dimensions(kind: SamplerKind, samples: usize) -> Vec<f64> {
+        (0..samples as u64)
+            .map(|index| kind.start(7, 3, index, samples).get_1d())
+            .collect()
+    }
+
+    #[test]
+    fn samples_stay_in_the_unit_interval() {
+        for kind in KINDS {
+            let mut sampler = kind.start(1, 2, 3, 16);
+            for _ in 0..200 {
+                let [u, v] = sampler.get_2d();
+                let w = sampler.get_1d();
+                assert!(
+                    [u, v, w].iter().all(|x| (0.0..1.0).contains(x)),
+                    "{:?}",
+                    kind
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn samples_only_depend_on_their_position() {
+        for kind in KINDS {
+            let mut first = kind.start(5, 10, 3, 16);
+            let mut second = kind.start(5, 10, 3, 16);
+            for _ in 0..20 {
+                assert_eq!(first.get_2d(), second.get_2d(), "{:?}", kind);
+            }
+            assert_ne!(
+                kind.start(5, 10, 3, 16).get_2d(),
+                kind.start(5, 11, 3, 16).get_2d(),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_fill_every_stratum() {
+        for kind in [
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            let mut strata = first_dimensions(kind, 16)
+                .iter()
+                .map(|x| (x * 16.) as usize)
+                .collect::<Vec<_>>();
+            strata.sort_unstable();
+
+            assert_eq!(strata, (0..16).collect::<Vec<_>>(), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_estimate_integrals_better() {
+        // The mean of x² over [0, 1) is a third
+        let error = |kind: SamplerKind| {
+            (0..64)
+                .map(|pixel| {
+                    let estimate = (0..16)
+                        .map(|index| kind.start(0, pixel, index, 16).get_2d()[1].powi(2))
+                        .sum::<f64>()
+                        / 16.;
+                    (estimate - 1. / 3.).powi(2)
+                })
+                .sum::<f64>()
+        };
+        let independent = error(SamplerKind::Independent);
+
+        for kind in [
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ] {
+            assert!(error(kind) < independent / 4., "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use super::{hashed_unit, Sampler};
+
+/// Bases for the dimensions of the Halton sequence. Past these, dimensions are independent.
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Sample `index` of the Halton sequence, using one prime base per dimension. Each pixel's
+/// sequence is shifted by its own random amount in every dimension (a Cranley–Patterson
+/// rotation), so neighbouring pixels don't share the same pattern.
+pub struct HaltonSampler {
+    seed: u64,
+    index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64, index: u64) -> Self {
+        Self {
+            seed,
+            index,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let shift = hashed_unit(self.seed, 0, dimension as u64);
+                (radical_inverse(base, self.index) + shift).fract()
+            }
+            None => hashed_unit(self.seed, self.index, dimension as u64),
+        }
+    }
+}
+
+/// Mirror the digits of `index` in `base` about the radix point
+fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inverse_base = 1. / base as f64;
+    let mut digits = 0;
+    let mut scale = 1.;
+    while index > 0 {
+        digits = digits * base + index % base;
+        scale *= inverse_base;
+        index /= base;
+    }
+    (digits as f64 * scale).min(1. - f64::EPSILON / 2.)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn radical_inverse_mirrors_digits() {
+        assert_eq!(radical_inverse(2, 0), 0.);
+        assert_eq!(radical_inverse(2, 1), 0.5);
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7. / 9.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dimensions_past_the_primes_still_vary() {
+        let mut sampler = HaltonSampler::new(0, 3);
+        (0..PRIMES.len()).for_each(|_| {
+            sampler.get_1d();
+        });
+
+        assert_ne!(sampler.get_1d(), sampler.get_1d());
+    }
+}
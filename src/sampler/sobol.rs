@@ -0,0 +1,110 @@
+use super::{mix, Sampler};
+
+/// The first two dimensions of the Sobol sequence, used over again for every pair of dimensions
+/// a path asks for. Each pair shuffles the order of the points and Owen scrambles their
+/// coordinates with its own seeds, following Burley's "Practical Hash-based Owen Scrambling",
+/// so the pairs aren't correlated with each other but every power of two samples still covers
+/// each pair evenly.
+pub struct SobolSampler {
+    seed: u64,
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64, index: u64) -> Self {
+        Self {
+            seed,
+            index: index as u32,
+            dimension: 0,
+        }
+    }
+
+    /// The shuffled point for the pair of dimensions starting at `dimension`
+    fn point(&mut self, dimension: u64) -> [u32; 2] {
+        let seed = |salt: u64| mix(self.seed ^ mix(dimension * 3 + salt)) as u32;
+        let index = nested_uniform_scramble(self.index, seed(0));
+        [
+            nested_uniform_scramble(index.reverse_bits(), seed(1)),
+            nested_uniform_scramble(sobol_second_dimension(index), seed(2)),
+        ]
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        to_unit(self.point(dimension)[0])
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        let dimension = self.dimension;
+        self.dimension += 2;
+        self.point(dimension).map(to_unit)
+    }
+}
+
+fn to_unit(bits: u32) -> f64 {
+    bits as f64 / (1u64 << 32) as f64
+}
+
+/// The second Sobol dimension, whose direction numbers come from the polynomial `x + 1`
+fn sobol_second_dimension(mut index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Laine and Karras's hash, which like Owen scrambling only lets each bit depend on the bits
+/// below it
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+/// Owen scrambling, where each bit is flipped depending on the bits above it
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn second_dimension_matches_known_points() {
+        let points = (0..4)
+            .map(|index| to_unit(sobol_second_dimension(index)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(points, vec![0., 0.5, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn scrambled_pairs_fill_every_elementary_interval() {
+        // Sixteen points should leave one in each cell of a 4 by 4 grid, and of a 2 by 8 grid
+        for (columns, rows) in [(4., 4.), (2., 8.)] {
+            let mut cells = (0..16)
+                .map(|index| {
+                    let [u, v] = SobolSampler::new(11, index).get_2d();
+                    ((v * rows) as usize * columns as usize) + (u * columns) as usize
+                })
+                .collect::<Vec<_>>();
+            cells.sort_unstable();
+
+            assert_eq!(cells, (0..16).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{hashed_unit, mix, Sampler};
+
+/// Splits every dimension into as many strata as there are samples per pixel, or every pair of
+/// dimensions into a square grid with at least that many cells, and gives each sample its own
+/// stratum. Which stratum a sample gets is shuffled differently for each dimension, so that the
+/// dimensions aren't correlated with each other.
+pub struct StratifiedSampler {
+    seed: u64,
+    index: u64,
+    samples: u64,
+    dimension: u64,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, index: u64, samples: u64) -> Self {
+        Self {
+            seed,
+            index,
+            samples: samples.max(1),
+            dimension: 0,
+        }
+    }
+
+    /// Which of `count` strata this sample takes in `dimension`
+    fn stratum(&self, count: u64, dimension: u64) -> u64 {
+        let seed = mix(self.seed ^ mix(dimension)) as u32;
+        permutation_element((self.index % count) as u32, count as u32, seed) as u64
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        let stratum = self.stratum(self.samples, dimension);
+        (stratum as f64 + hashed_unit(self.seed, self.index, dimension)) / self.samples as f64
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        let dimension = self.dimension;
+        self.dimension += 2;
+        let side = (self.samples as f64).sqrt().ceil() as u64;
+        let stratum = self.stratum(side * side, dimension);
+        let jitter = |offset| hashed_unit(self.seed, self.index, dimension + offset);
+        [
+            ((stratum % side) as f64 + jitter(0)) / side as f64,
+            ((stratum / side) as f64 + jitter(1)) / side as f64,
+        ]
+    }
+}
+
+/// Element `index` of a random permutation of `0..count` picked by `seed`, without building the
+/// permutation. This is Kensler's hash from "Correlated Multi-Jittered Sampling".
+fn permutation_element(index: u32, count: u32, seed: u32) -> u32 {
+    let mut mask = count.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    let mut i = index;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < count {
+            break;
+        }
+    }
+    i.wrapping_add(seed) % count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn permutations_use_every_element_once() {
+        for count in [1, 5, 16, 100] {
+            let mut elements = (0..count)
+                .map(|index| permutation_element(index, count, 0xdead_beef))
+                .collect::<Vec<_>>();
+            elements.sort_unstable();
+
+            assert_eq!(elements, (0..count).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn pairs_fill_every_cell_of_the_grid() {
+        let mut cells = (0..9)
+            .map(|index| {
+                let [u, v] = StratifiedSampler::new(3, index, 9).get_2d();
+                (v * 3.) as usize * 3 + (u * 3.) as usize
+            })
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+
+        assert_eq!(cells, (0..9).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,350 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{Color, HitEnum, HitableList, Model, Plane, Sphere, Vec3};
+use crate::material::{Dialectric, Lambertian, Metal};
+use crate::output::ToneMapping;
+use crate::renderer::{Background, CameraDescription, Filter, PointLight};
+use crate::sampler::SamplerKind;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SceneData", into = "SceneData")]
+pub struct SceneDescription {
+    pub settings: RenderSettings,
+    pub camera: CameraDescription,
+    pub background: Background,
+    /// Lights with no size, on top of any emissive objects
+    pub lights: Vec<PointLight>,
+    /// Objects that transforms in `hittable` place by name, sharing one copy between them
+    pub objects: BTreeMap<String, Arc<HitEnum>>,
+    pub hittable: HitableList,
+}
+
+/// How a scene is written in files, where the names transforms place are only looked up once
+/// everything has been read
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneData {
+    #[serde(default)]
+    settings: RenderSettings,
+    #[serde(default)]
+    camera: CameraDescription,
+    #[serde(default)]
+    background: Background,
+    #[serde(default)]
+    lights: Vec<PointLight>,
+    /// Each can also place the others by name, as long as none of them ends up inside itself
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    objects: BTreeMap<String, Arc<HitEnum>>,
+    hittable: HitableList,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderSettings {
+    pub image_width: u32,
+    pub image_height: u32,
+    pub samples_per_pixel: usize,
+    /// Samples every pixel gets before adaptive sampling checks whether it has converged
+    pub min_samples_per_pixel: usize,
+    /// Relative error below which a pixel stops being sampled, or `None` to always take
+    /// `samples_per_pixel`
+    pub noise_threshold: Option<f64>,
+    pub max_depth: usize,
+    /// Bounces every path makes before Russian roulette can end it early
+    pub roulette_depth: usize,
+    /// Where the numbers behind each sample come from
+    pub sampler: SamplerKind,
+    /// How samples are weighted into the pixels around them
+    pub filter: Filter,
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            image_width: 400,
+            image_height: 225,
+            samples_per_pixel: 100,
+            min_samples_per_pixel: 16,
+            noise_threshold: None,
+            max_depth: 50,
+            roulette_depth: 5,
+            sampler: SamplerKind::default(),
+            filter: Filter::default(),
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+}
+
+impl SceneDescription {
+    /// Read a scene file. Paths to models in it are relative to the file's directory.
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let contents =
+            fs::read_to_string(path).map_err(|err| SceneError::Io(path.to_owned(), err))?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        Model::in_directory(directory, || serde_json::from_str(&contents))
+            .map_err(|err| SceneError::Parse(path.to_owned(), err))
+    }
+}
+
+impl Default for SceneDescription {
+    fn default() -> Self {
+        let ground_material = Lambertian::new(Color::new(0.8, 0.8, 0.0));
+        let center_material = Lambertian::new(Color::new(0.7, 0.3, 0.3));
+        let left_material = Dialectric::new(1.5);
+        let right_material = Metal::new(Color::new(0.8, 0.6, 0.2), Some(1.0));
+        let mut world = HitableList::new();
+        world.add(Plane::new(-0.5 * Vec3::j(), Vec3::j(), ground_material));
+        world.add(Sphere::new((0.0, 0.0, -1.0).into(), 0.5, center_material));
+        world.add(Sphere::new(
+            (-1., 0.0, -1.0).into(),
+            0.5,
+            left_material.clone(),
+        ));
+        world.add(Sphere::new((-1., 0.0, -1.0).into(), -0.4, left_material));
+        world.add(Sphere::new((1.0, 0.0, -1.0).into(), 0.5, right_material));
+        Self {
+            settings: RenderSettings::default(),
+            camera: CameraDescription::default(),
+            background: Background::default(),
+            lights: Vec::new(),
+            objects: BTreeMap::new(),
+            hittable: world,
+        }
+    }
+}
+
+impl TryFrom<SceneData> for SceneDescription {
+    type Error = String;
+
+    fn try_from(data: SceneData) -> Result<Self, Self::Error> {
+        let objects = resolve_objects(data.objects)?;
+        let hittable = data.hittable.resolve(&objects)?;
+        Ok(Self {
+            settings: data.settings,
+            camera: data.camera,
+            background: data.background,
+            lights: data.lights,
+            objects,
+            hittable,
+        })
+    }
+}
+
+impl From<SceneDescription> for SceneData {
+    fn from(scene: SceneDescription) -> Self {
+        Self {
+            settings: scene.settings,
+            camera: scene.camera,
+            background: scene.background,
+            lights: scene.lights,
+            objects: scene.objects,
+            hittable: scene.hittable,
+        }
+    }
+}
+
+/// Look up the names the scene's objects place inside each other, starting from the ones that
+/// don't place any
+fn resolve_objects(
+    mut unresolved: BTreeMap<String, Arc<HitEnum>>,
+) -> Result<BTreeMap<String, Arc<HitEnum>>, String> {
+    let mut objects = BTreeMap::new();
+    while !unresolved.is_empty() {
+        let ready = unresolved.iter().find_map(|(name, object)| {
+            let names = object.unresolved_names();
+            names
+                .iter()
+                .all(|name| objects.contains_key(*name))
+                .then(|| name.clone())
+        });
+        let Some(name) = ready else {
+            let missing = unresolved
+                .values()
+                .flat_map(|object| object.unresolved_names())
+                .find(|name| !unresolved.contains_key(*name) && !objects.contains_key(*name));
+            return Err(match missing {
+                Some(name) => format!(
+                    "no object named {:?} is defined in the scene's objects",
+                    name
+                ),
+                None => format!(
+                    "the objects {:?} end up placing themselves",
+                    unresolved.keys().collect::<Vec<_>>()
+                ),
+            });
+        };
+        let object = Arc::unwrap_or_clone(unresolved.remove(&name).unwrap());
+        let object = match object.resolve(&objects)? {
+            // Put into a `Bvh` once here rather than by every transform placing it
+            HitEnum::List(list) => HitEnum::Bvh(list.into()),
+            object => object,
+        };
+        objects.insert(name, Arc::new(object));
+    }
+    Ok(objects)
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "Could not read scene file {:?}: {}", path, err),
+            Self::Parse(path, err) => write!(f, "Invalid scene file {:?}: {}", path, err),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Hittable;
+
+    #[test]
+    fn default_scene_round_trips_through_json() {
+        let json = serde_json::to_string(&SceneDescription::default()).unwrap();
+        let scene: SceneDescription = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(json, serde_json::to_string(&scene).unwrap());
+    }
+
+    #[test]
+    fn example_scene_parses() {
+        serde_json::from_str::<SceneDescription>(include_str!("../scenes/default.json")).unwrap();
+    }
+
+    /// Loaded by path, so the models they use are found next to them
+    fn load_example(name: &str) -> SceneDescription {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("scenes")
+            .join(name);
+        SceneDescription::load(&path).unwrap()
+    }
+
+    #[test]
+    fn model_scene_parses() {
+        load_example("model.json");
+    }
+
+    #[test]
+    fn night_scene_parses() {
+        serde_json::from_str::<SceneDescription>(include_str!("../scenes/night.json")).unwrap();
+    }
+
+    #[test]
+    fn instances_scene_parses() {
+        load_example("instances.json");
+    }
+
+    #[test]
+    fn named_objects_are_shared_by_the_transforms_placing_them() {
+        let first = load_example("instances.json");
+        let second = load_example("instances.json");
+
+        for scene in [first, second] {
+            assert_eq!(Arc::strong_count(&scene.objects["pyramid"]), 3);
+            let json = serde_json::to_value(&scene).unwrap();
+            assert_eq!(
+                json["hittable"]["list"][1]["Transform"]["object"],
+                "pyramid"
+            );
+        }
+    }
+
+    fn ball() -> serde_json::Value {
+        serde_json::json!({ "Sphere": {
+            "center": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "radius": 1.0,
+            "material": { "Lambertian": { "albedo": { "x": 1.0, "y": 1.0, "z": 1.0 } } }
+        } })
+    }
+
+    fn placing(name: &str) -> serde_json::Value {
+        serde_json::json!({ "Transform": {
+            "object": name,
+            "translate": { "x": 0.0, "y": 0.0, "z": -3.0 }
+        } })
+    }
+
+    #[test]
+    fn objects_can_come_after_what_places_them() {
+        let json = format!(
+            r#"{{ "hittable": {{ "list": [{}] }}, "objects": {{ "outer": {}, "inner": {} }} }}"#,
+            placing("outer"),
+            placing("inner"),
+            ball()
+        );
+
+        let scene: SceneDescription = serde_json::from_str(&json).unwrap();
+
+        let ray = crate::geometry::Ray::new(Vec3::zeros(), -Vec3::k());
+        let record = scene.hittable.hit(&ray, 0.001..f64::INFINITY).unwrap();
+        assert!((record.t - 5.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_and_looping_names_are_rejected() {
+        for (objects, message) in [
+            (serde_json::json!({}), "no object named \"a\""),
+            (
+                serde_json::json!({ "a": placing("b"), "b": placing("a") }),
+                "end up placing themselves",
+            ),
+        ] {
+            let json = serde_json::json!({
+                "objects": objects,
+                "hittable": { "list": [placing("a")] }
+            });
+
+            let err = serde_json::from_value::<SceneDescription>(json)
+                .err()
+                .unwrap();
+
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+    }
+
+    #[test]
+    fn cornell_scene_parses() {
+        serde_json::from_str::<SceneDescription>(include_str!("../scenes/cornell.json")).unwrap();
+    }
+
+    #[test]
+    fn missing_settings_use_defaults() {
+        let scene: SceneDescription =
+            serde_json::from_str(r#"{"hittable": {"list": []}}"#).unwrap();
+
+        assert_eq!(
+            scene.settings.image_width,
+            RenderSettings::default().image_width
+        );
+        assert_eq!(
+            scene.settings.max_depth,
+            RenderSettings::default().max_depth
+        );
+    }
+
+    #[test]
+    fn unknown_variants_are_reported() {
+        let err =
+            serde_json::from_str::<SceneDescription>(r#"{"hittable": {"list": [{"Cube": {}}]}}"#)
+                .err()
+                .unwrap();
+
+        assert!(err.to_string().contains("unknown variant `Cube`"));
+    }
+}